@@ -0,0 +1,15 @@
+// Run with: cargo fuzz run valid_ibans
+#![no_main]
+use iban_validator_lib::strategies::ArbitraryIban;
+use iban_validator_lib::{get_iban_vo_from_str, pseudonymize};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (ArbitraryIban, Vec<u8>, bool)| {
+    let (ArbitraryIban(iban), key, keep_bank_identifier) = input;
+    assert!(get_iban_vo_from_str(&iban).is_valid(), "{}", iban);
+
+    // The pseudonym of a valid IBAN is a valid IBAN of the same country
+    let pseudonym = pseudonymize::pseudonymize(&iban, &key, keep_bank_identifier).unwrap();
+    assert!(get_iban_vo_from_str(&pseudonym).is_valid(), "{}", pseudonym);
+    assert_eq!(pseudonym[..2], iban[..2]);
+});
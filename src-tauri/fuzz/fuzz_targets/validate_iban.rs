@@ -0,0 +1,27 @@
+// Run with: cargo fuzz run validate_iban
+#![no_main]
+use iban_validator_lib::iban::Iban;
+use iban_validator_lib::{explain, get_iban_vo_from_str, partial, text_scan};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    // No input may make the validation panic
+    let iban_vo = get_iban_vo_from_str(data);
+    let partial_vo = partial::get_partial_iban_vo_from_str(data);
+    let explanation = explain::explain_checksum(data);
+    text_scan::find_ibans(data);
+    let iban = data.parse::<Iban>();
+
+    // A valid IBAN is complete as you type it, and its checksum can be explained
+    if iban_vo.is_valid() {
+        assert!(partial_vo.is_complete && partial_vo.can_become_valid, "{}", data);
+        assert!(explanation.is_ok(), "{}", data);
+    }
+    // A parsed IBAN passes the validation, and its parts can be taken
+    if let Ok(iban) = iban {
+        assert!(get_iban_vo_from_str(iban.electronic_str()).is_valid(), "{}", data);
+        assert_eq!(iban.to_string().parse::<Iban>().as_ref(), Ok(&iban), "{}", data);
+        iban.bank_identifier();
+        iban.qr_iid();
+    }
+});
@@ -0,0 +1,544 @@
+// German account numbers. The Bundesbank publishes the Bankleitzahlendatei, a file with a line of
+// fixed width fields per bank and branch. Every bank has a method for the check digit of its
+// account numbers (Prüfzifferberechnungsmethode) and a rule for converting its account numbers to
+// IBANs (IBAN-Regel). An IBAN with correct check digits can still have an account number that is
+// invalid for its bank, which the method finds.
+//
+// Only the common methods are implemented, accounts of banks with other methods are reported as
+// not checked. Of the IBAN rules the standard rule, the rule that forbids calculating the IBAN and
+// the rules 0002 to 0004 with exceptions for single accounts are implemented. The other bank
+// specific rules, which replace bank codes and account numbers from tables of the bank, are refused
+// instead of giving a wrong IBAN.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use serde::ser::{Serialize, Serializer};
+
+use crate::iso7064;
+use crate::national_check::AccountCheck;
+
+// The fields of a line of the Bankleitzahlendatei as (start, end) columns counted from 0
+const BLZ: (usize, usize) = (0, 8);
+const FEATURE: (usize, usize) = (8, 9);
+const NAME: (usize, usize) = (9, 67);
+const BIC: (usize, usize) = (139, 150);
+const METHOD: (usize, usize) = (150, 152);
+const DELETION: (usize, usize) = (159, 160);
+const SUCCESSOR: (usize, usize) = (160, 168);
+const IBAN_RULE: (usize, usize) = (168, 174);
+const STANDARD_IBAN_RULE: &str = "0000";
+const NO_IBAN_CALCULATION: &str = "0001";
+
+// Rule 0004: the donation accounts of the Landesbank Berlin have short numbers, the IBAN has the
+// real account number
+const DONATION_ACCOUNTS: [(&str, &str); 10] = [
+    ("135", "0990021440"),
+    ("1111", "6600012020"),
+    ("1900", "0920019005"),
+    ("7878", "0780008006"),
+    ("8888", "0250030942"),
+    ("9595", "1653524703"),
+    ("97097", "0013044150"),
+    ("112233", "0630025819"),
+    ("336666", "6604058903"),
+    ("484848", "0920018963"),
+];
+
+// The bank directory used for the national account check of German IBANs
+static DIRECTORY: RwLock<Option<Arc<BankDirectory>>> = RwLock::new(None);
+
+#[derive(PartialEq, Debug)]
+pub enum BundesbankError {
+    UnreadableFile(String),
+    InvalidLine(usize),
+    NoBankDirectory,
+    InvalidBankCode(String),
+    InvalidAccountNumber(String),
+    UnknownBank(String),
+    NoIbanCalculation(String),
+    NoIbanForAccount(String),
+    UnsupportedIbanRule(String),
+}
+
+impl fmt::Display for BundesbankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundesbankError::UnreadableFile(msg) => write!(f, "the bank code file could not be read: {}", msg),
+            BundesbankError::InvalidLine(line) => write!(f, "line {} of the bank code file is not a bank", line),
+            BundesbankError::NoBankDirectory => write!(f, "no bank code file has been loaded"),
+            BundesbankError::InvalidBankCode(blz) => write!(f, "{} is not an 8 digit bank code", blz),
+            BundesbankError::InvalidAccountNumber(account) => {
+                write!(f, "{} is not a valid account number for the bank", account)
+            }
+            BundesbankError::UnknownBank(blz) => write!(f, "there is no bank with the bank code {}", blz),
+            BundesbankError::NoIbanCalculation(blz) => {
+                write!(f, "the bank {} doesn't allow calculating its IBANs, ask the account holder", blz)
+            }
+            BundesbankError::NoIbanForAccount(account) => {
+                write!(f, "the IBAN of the account {} can't be calculated, ask the account holder", account)
+            }
+            BundesbankError::UnsupportedIbanRule(rule) => {
+                write!(f, "the IBAN rule {} of the bank is not supported", rule)
+            }
+        }
+    }
+}
+
+impl Serialize for BundesbankError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bank {
+    pub name: String,
+    pub bic: Option<String>,
+    pub method: String,
+    // The rule number and its version, e.g. 000000
+    pub iban_rule: String,
+    pub is_deleted: bool,
+    pub successor: Option<String>,
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct BankDirectory {
+    banks: HashMap<String, Bank>,
+}
+
+impl BankDirectory {
+    // The file is encoded in ISO 8859-1, where every byte is the character with that code
+    pub fn parse(bytes: &[u8]) -> Result<BankDirectory, BundesbankError> {
+        let text: String = bytes.iter().map(|&byte| byte as char).collect();
+        let mut banks = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line: Vec<char> = line.chars().collect();
+            if line.is_empty() {
+                continue;
+            }
+            if line.len() < SUCCESSOR.1 {
+                return Err(BundesbankError::InvalidLine(i + 1));
+            }
+            let field = |(start, end): (usize, usize)| -> String {
+                line.get(start..end.min(line.len())).unwrap_or(&[]).iter().collect::<String>().trim().to_string()
+            };
+            let blz = field(BLZ);
+            if blz.len() != 8 || !blz.chars().all(|c| c.is_ascii_digit()) {
+                return Err(BundesbankError::InvalidLine(i + 1));
+            }
+            let non_empty = |value: String| Some(value).filter(|value| !value.is_empty() && value != "00000000");
+            let bank = Bank {
+                name: field(NAME),
+                bic: non_empty(field(BIC)),
+                method: field(METHOD),
+                iban_rule: Some(field(IBAN_RULE)).filter(|rule| !rule.is_empty()).unwrap_or_else(|| String::from("000000")),
+                is_deleted: field(DELETION) == "1",
+                successor: non_empty(field(SUCCESSOR)),
+            };
+            // Every branch has a line, the bank itself is the one with the feature 1
+            if field(FEATURE) == "1" || !banks.contains_key(&blz) {
+                banks.insert(blz, bank);
+            }
+        }
+        Ok(BankDirectory { banks })
+    }
+
+    pub fn load(path: &Path) -> Result<BankDirectory, BundesbankError> {
+        let bytes = std::fs::read(path).map_err(|e| BundesbankError::UnreadableFile(e.to_string()))?;
+        BankDirectory::parse(&bytes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.banks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banks.is_empty()
+    }
+
+    pub fn bank(&self, blz: &str) -> Option<&Bank> {
+        self.banks.get(blz)
+    }
+
+    pub fn check_account(&self, blz: &str, account_number: &str) -> AccountCheck {
+        let bank = self.bank(blz);
+        AccountCheck {
+            bank_code: blz.to_string(),
+            account_number: account_number.to_string(),
+            bank_name: bank.map(|bank| bank.name.clone()),
+            bic: bank.and_then(|bank| bank.bic.clone()),
+            method: bank.map(|bank| bank.method.clone()),
+            is_valid: bank.and_then(|bank| check_account_number(&bank.method, account_number)),
+        }
+    }
+
+    // The check of the BBAN of a German IBAN, the bank code is followed by the account number
+    pub fn check_bban(&self, bban: &str) -> Option<AccountCheck> {
+        Some(self.check_account(bban.get(0..8)?, bban.get(8..)?))
+    }
+
+    // The IBAN of a German account number, following the IBAN rule of the bank
+    pub fn to_iban(&self, blz: &str, account_number: &str) -> Result<String, BundesbankError> {
+        let blz: String = blz.split_whitespace().collect();
+        let account_number: String = account_number.split_whitespace().collect();
+        if blz.len() != 8 || !blz.chars().all(|c| c.is_ascii_digit()) {
+            return Err(BundesbankError::InvalidBankCode(blz));
+        }
+        let bank = self.bank(&blz).ok_or_else(|| BundesbankError::UnknownBank(blz.clone()))?;
+        let rule = bank.iban_rule.get(..4).unwrap_or("");
+        // The donation accounts are replaced before the check, their short numbers have no check digit
+        let donation_account = DONATION_ACCOUNTS
+            .iter()
+            .find(|(short, _)| rule == "0004" && *short == account_number.trim_start_matches('0'));
+        let account_number = match donation_account {
+            Some((_, account)) => account.to_string(),
+            None => {
+                if check_account_number(&bank.method, &account_number) == Some(false) || account_number.len() > 10 {
+                    return Err(BundesbankError::InvalidAccountNumber(account_number));
+                }
+                account_number
+            }
+        };
+        let digits = format!("{:0>10}", account_number);
+        match rule {
+            STANDARD_IBAN_RULE | "0004" => {}
+            NO_IBAN_CALCULATION => return Err(BundesbankError::NoIbanCalculation(blz)),
+            // Deutsche Bank: accounts with 86 or 6 at the positions 7 and 8 have no IBAN
+            "0002" if &digits[6..8] == "86" || &digits[6..7] == "6" => {
+                return Err(BundesbankError::NoIbanForAccount(account_number))
+            }
+            "0002" => {}
+            "0003" if digits == "6161604670" => return Err(BundesbankError::NoIbanForAccount(account_number)),
+            "0003" => {}
+            _ => return Err(BundesbankError::UnsupportedIbanRule(bank.iban_rule.clone())),
+        }
+        // The IBANs of banks that are being deleted get the bank code of the bank that takes over
+        let blz = match (&bank.successor, bank.is_deleted) {
+            (Some(successor), true) => successor.clone(),
+            _ => blz,
+        };
+        let bban = format!("{}{:0>10}", blz, account_number);
+        let check_digits = iso7064::System::Mod97_10
+            .compute(&(bban.clone() + "DE"))
+            .map_err(|_| BundesbankError::InvalidAccountNumber(account_number))?;
+        Ok(format!("DE{}{}", check_digits, bban))
+    }
+}
+
+pub fn load_bank_directory(path: &Path) -> Result<usize, BundesbankError> {
+    let directory = BankDirectory::load(path)?;
+    let banks = directory.len();
+    *DIRECTORY.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(directory));
+    Ok(banks)
+}
+
+pub fn bank_directory() -> Result<Arc<BankDirectory>, BundesbankError> {
+    DIRECTORY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or(BundesbankError::NoBankDirectory)
+}
+
+// The account number as 10 digits, the positions of the methods count from 1 at the left
+fn account_digits(account_number: &str) -> Option<[u32; 10]> {
+    if account_number.is_empty() || account_number.len() > 10 {
+        return None;
+    }
+    let mut digits = [0; 10];
+    let offset = 10 - account_number.len();
+    for (i, c) in account_number.chars().enumerate() {
+        digits[offset + i] = c.to_digit(10)?;
+    }
+    Some(digits)
+}
+
+// The weighted sum of the positions first..=last, with the weights starting at the last position
+// and repeating. With `cross_sum` the digits of every product are added instead of the product.
+fn weighted_sum(digits: &[u32; 10], first: usize, last: usize, weights: &[u32], cross_sum: bool) -> u32 {
+    let positions: Vec<usize> = (first..=last).rev().collect();
+    weighted_sum_of(digits, &positions, weights, cross_sum)
+}
+
+// The weighted sum of positions that are not next to each other, from right to left
+fn weighted_sum_of(digits: &[u32; 10], positions: &[usize], weights: &[u32], cross_sum: bool) -> u32 {
+    positions
+        .iter()
+        .zip(weights.iter().cycle())
+        .map(|(position, weight)| {
+            let product = digits[*position - 1] * weight;
+            if cross_sum {
+                product / 10 + product % 10
+            } else {
+                product
+            }
+        })
+        .sum()
+}
+
+fn modulus_10(sum: u32) -> Option<u32> {
+    Some((10 - sum % 10) % 10)
+}
+
+// A remainder of 1 would need the check digit 10, such account numbers are invalid
+fn modulus_11(sum: u32) -> Option<u32> {
+    match sum % 11 {
+        0 => Some(0),
+        1 => None,
+        remainder => Some(11 - remainder),
+    }
+}
+
+// A remainder of 0 or 1 gives the check digit 0
+fn modulus_11_zero(sum: u32) -> Option<u32> {
+    match sum % 11 {
+        0 | 1 => Some(0),
+        remainder => Some(11 - remainder),
+    }
+}
+
+// Whether the check digit at the position is the one calculated over the positions before it
+fn check(
+    digits: &[u32; 10],
+    (first, last): (usize, usize),
+    weights: &[u32],
+    cross_sum: bool,
+    check_digit: fn(u32) -> Option<u32>,
+) -> bool {
+    check_digit(weighted_sum(digits, first, last, weights, cross_sum)) == Some(digits[last])
+}
+
+// Moves the account number two positions to the left, for account numbers without a sub account
+fn shift_left(digits: &[u32; 10]) -> [u32; 10] {
+    let mut shifted = [0; 10];
+    shifted[..8].copy_from_slice(&digits[2..]);
+    shifted
+}
+
+// Checks the account number with the method of the Bundesbank, `None` for methods that are not
+// implemented
+pub fn check_account_number(method: &str, account_number: &str) -> Option<bool> {
+    const W2_7: &[u32] = &[2, 3, 4, 5, 6, 7];
+    const W2_10: &[u32] = &[2, 3, 4, 5, 6, 7, 8, 9, 10];
+    let digits = match account_digits(account_number) {
+        Some(digits) => digits,
+        None => return Some(false),
+    };
+    let valid = match method {
+        "00" => check(&digits, (1, 9), &[2, 1], true, modulus_10),
+        "01" => check(&digits, (1, 9), &[3, 7, 1], false, modulus_10),
+        "02" => check(&digits, (1, 9), &[2, 3, 4, 5, 6, 7, 8, 9, 2], false, modulus_11),
+        "03" => check(&digits, (1, 9), &[2, 1], false, modulus_10),
+        "04" => check(&digits, (1, 9), W2_7, false, modulus_11),
+        "05" => check(&digits, (1, 9), &[7, 3, 1], false, modulus_10),
+        "06" => check(&digits, (1, 9), W2_7, false, modulus_11_zero),
+        "07" => check(&digits, (1, 9), W2_10, false, modulus_11),
+        // Account numbers below 60000 have no check digit
+        "08" => account_number.parse::<u64>().is_ok_and(|n| n < 60000) || check(&digits, (1, 9), &[2, 1], true, modulus_10),
+        "09" => true,
+        "10" => check(&digits, (1, 9), W2_10, false, modulus_11_zero),
+        "11" => check(&digits, (1, 9), W2_10, false, |sum| match sum % 11 {
+            0 => Some(0),
+            1 => Some(9),
+            remainder => Some(11 - remainder),
+        }),
+        // The check digit is at position 8, followed by a sub account that may be left out
+        "13" => {
+            check(&digits, (2, 7), &[2, 1], true, modulus_10)
+                || check(&shift_left(&digits), (2, 7), &[2, 1], true, modulus_10)
+        }
+        // The check digit is at position 8, the sum is reduced by 1
+        "17" => check(&digits, (2, 7), &[2, 1], true, |sum| match (sum + 10) % 11 {
+            0 => Some(0),
+            remainder => Some(10 - remainder),
+        }),
+        "20" => check(&digits, (1, 9), &[2, 3, 4, 5, 6, 7, 8, 9, 3], false, modulus_11_zero),
+        "26" => {
+            let digits = if digits[0] == 0 && digits[1] == 0 { shift_left(&digits) } else { digits };
+            check(&digits, (1, 7), &[2, 3, 4, 5, 6, 7, 2], false, modulus_11_zero)
+        }
+        "32" => check(&digits, (4, 9), W2_7, false, modulus_11_zero),
+        "33" => check(&digits, (5, 9), &[2, 3, 4, 5, 6], false, modulus_11_zero),
+        "34" => check(&digits, (1, 7), &[2, 4, 8, 5, 10, 9, 7], false, modulus_11_zero),
+        "38" => check(&digits, (4, 9), &[2, 4, 8, 5, 10, 9], false, modulus_11_zero),
+        "44" => check(&digits, (5, 9), &[2, 4, 8, 5, 10], false, modulus_11_zero),
+        "46" => check(&digits, (3, 7), &[2, 3, 4, 5, 6], false, modulus_11_zero),
+        // The check digit is at position 8, with an 8 at position 9 the positions 9 and 10 count too
+        "61" => {
+            let positions: &[usize] = if digits[8] == 8 { &[10, 9, 7, 6, 5, 4, 3, 2, 1] } else { &[7, 6, 5, 4, 3, 2, 1] };
+            modulus_10(weighted_sum_of(&digits, positions, &[2, 1], true)) == Some(digits[7])
+        }
+        // Position 1 is always 0, account numbers of 7 digits have no sub account
+        "63" => {
+            let digits = if digits[..3] == [0, 0, 0] { shift_left(&digits) } else { digits };
+            digits[0] == 0 && check(&digits, (2, 7), &[2, 1], true, modulus_10)
+        }
+        // With a 9 at position 3 the positions 3 to 9 count
+        "88" if digits[2] == 9 => check(&digits, (3, 9), &[2, 3, 4, 5, 6, 7, 8], false, modulus_11_zero),
+        "88" => check(&digits, (4, 9), W2_7, false, modulus_11_zero),
+        // The check digit is at position 7, in four variants
+        "91" => {
+            check(&digits, (1, 6), W2_7, false, modulus_11_zero)
+                || check(&digits, (1, 6), &[7, 6, 5, 4, 3, 2], false, modulus_11_zero)
+                || modulus_11_zero(weighted_sum_of(&digits, &[10, 9, 8, 6, 5, 4, 3, 2, 1], W2_10, false))
+                    == Some(digits[6])
+                || check(&digits, (1, 6), &[2, 4, 8, 5, 10, 9], false, modulus_11_zero)
+        }
+        // Method 06, without a check digit in the range 0396000000 to 0499999999
+        "99" => {
+            (396000000..=499999999).contains(&account_number.parse::<u64>().unwrap_or(0))
+                || check(&digits, (1, 9), W2_7, false, modulus_11_zero)
+        }
+        // Method 00, otherwise method 04
+        "A2" => {
+            check(&digits, (1, 9), &[2, 1], true, modulus_10) || check(&digits, (1, 9), W2_7, false, modulus_11)
+        }
+        // Method 00 for account numbers of 10 digits, no check digit from 0010000000 to 0099999999
+        "D8" if digits[0] != 0 => check(&digits, (1, 9), &[2, 1], true, modulus_10),
+        "D8" => digits[1] == 0 && digits[2] != 0,
+        // Method 00 with 7 added to the sum
+        "E0" => check(&digits, (1, 9), &[2, 1], true, |sum| modulus_10(sum + 7)),
+        _ => return None,
+    };
+    Some(valid)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use super::*;
+
+    // A line of the Bankleitzahlendatei with the fields that are read
+    fn line(blz: &str, feature: &str, name: &str, bic: &str, method: &str, iban_rule: &str) -> String {
+        deleted_line(blz, feature, name, bic, method, iban_rule, "")
+    }
+
+    fn deleted_line(blz: &str, feature: &str, name: &str, bic: &str, method: &str, iban_rule: &str, successor: &str) -> String {
+        let deletion = if successor.is_empty() { "0" } else { "1" };
+        format!(
+            "{}{}{:<58}{:<5}{:<35}{:<27}{:<5}{:<11}{}{:<6}{}{}{:0<8}{}",
+            blz, feature, name, "50667", "Köln", name, "", bic, method, "000001", "U", deletion, successor, iban_rule
+        )
+    }
+
+    // The bank code file of the test banks
+    pub(crate) fn directory_file() -> Vec<u8> {
+        let file = [
+            line("37040044", "1", "Commerzbank", "COBADEFFXXX", "13", "000503"),
+            line("37040044", "2", "Commerzbank Filiale", "", "13", "000503"),
+            line("12345678", "1", "Testbank", "TESTDEFFXXX", "00", "000000"),
+            line("87654321", "1", "Andere Bank", "", "06", "000100"),
+            line("11111111", "1", "Neue Bank", "", "E4", "000000"),
+            line("10070000", "1", "Deutsche Bank", "", "09", "000200"),
+            line("51010400", "1", "Testbank Frankfurt", "", "09", "000300"),
+            line("10050000", "1", "Landesbank Berlin", "", "00", "000400"),
+            deleted_line("22222222", "1", "Alte Bank", "", "00", "000000", "12345678"),
+        ]
+        .join("\r\n");
+        // The file is in ISO 8859-1, ö is the byte F6
+        file.chars().map(|c| c as u32 as u8).collect()
+    }
+
+    pub(crate) fn directory() -> BankDirectory {
+        BankDirectory::parse(&directory_file()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let directory = directory();
+        assert_eq!(directory.len(), 8);
+        let bank = directory.bank("37040044").unwrap();
+        assert_eq!(bank.name, "Commerzbank");
+        assert_eq!(bank.bic.as_deref(), Some("COBADEFFXXX"));
+        assert_eq!((bank.method.as_str(), bank.iban_rule.as_str()), ("13", "000503"));
+        assert_eq!(BankDirectory::parse(b"3704004"), Err(BundesbankError::InvalidLine(1)));
+    }
+
+    #[test]
+    fn test_methods() {
+        for account in ["9290701", "539290858", "1501824", "1501832"] {
+            assert_eq!(check_account_number("00", account), Some(true), "{}", account);
+        }
+        assert_eq!(check_account_number("00", "1501833"), Some(false));
+        for account in ["94012341", "5073321010"] {
+            assert_eq!(check_account_number("06", account), Some(true), "{}", account);
+        }
+        // The account of DE89 3704 0044 0532 0130 00, with and without its sub account
+        assert_eq!(check_account_number("13", "0532013000"), Some(true));
+        assert_eq!(check_account_number("13", "5320130"), Some(true));
+        assert_eq!(check_account_number("13", "0532013100"), Some(true));
+        assert_eq!(check_account_number("13", "0532113000"), Some(false));
+        assert_eq!(check_account_number("09", "1"), Some(true));
+        assert_eq!(check_account_number("00", "12345678901"), Some(false));
+        assert_eq!(check_account_number("00", "12A"), Some(false));
+        assert_eq!(check_account_number("17", "0446786040"), Some(true));
+        assert_eq!(check_account_number("17", "0446786140"), Some(false));
+        assert_eq!(check_account_number("61", "2063099200"), Some(true));
+        assert_eq!(check_account_number("61", "0260760481"), Some(true));
+        assert_eq!(check_account_number("61", "2063099300"), Some(false));
+        assert_eq!(check_account_number("88", "2525259"), Some(true));
+        assert_eq!(check_account_number("88", "0901234560"), Some(true));
+        assert_eq!(check_account_number("88", "2525258"), Some(false));
+        assert_eq!(check_account_number("91", "2974118000"), Some(true));
+        assert_eq!(check_account_number("91", "2974119000"), Some(false));
+        assert_eq!(check_account_number("99", "0068007003"), Some(true));
+        assert_eq!(check_account_number("99", "0400000001"), Some(true));
+        assert_eq!(check_account_number("99", "0068007004"), Some(false));
+        assert_eq!(check_account_number("A2", "9290701"), Some(true));
+        assert_eq!(check_account_number("D8", "0010000000"), Some(true));
+        assert_eq!(check_account_number("D8", "0001000000"), Some(false));
+        assert_eq!(check_account_number("E0", "1234568013"), Some(true));
+        assert_eq!(check_account_number("E0", "1234568012"), Some(false));
+        assert_eq!(check_account_number("E4", "1234567890"), None);
+    }
+
+    #[test]
+    fn test_check_account() {
+        let check = directory().check_account("37040044", "0532013000");
+        assert_eq!(check.is_valid, Some(true));
+        assert_eq!(check.bank_name.as_deref(), Some("Commerzbank"));
+        assert_eq!(directory().check_account("11111111", "0532013000").is_valid, None);
+        assert_eq!(directory().check_account("99999999", "0532013000").is_valid, None);
+    }
+
+    #[test]
+    fn test_to_iban() {
+        let directory = directory();
+        assert_eq!(directory.to_iban("12345678", "9290701"), Ok(String::from("DE04123456780009290701")));
+        assert_eq!(directory.to_iban("22222222", "9290701"), directory.to_iban("12345678", "9290701"));
+        assert_eq!(
+            directory.to_iban("12345678", "9290702"),
+            Err(BundesbankError::InvalidAccountNumber(String::from("9290702")))
+        );
+        assert_eq!(
+            directory.to_iban("87654321", "94012341"),
+            Err(BundesbankError::NoIbanCalculation(String::from("87654321")))
+        );
+        assert_eq!(
+            directory.to_iban("37040044", "532013000"),
+            Err(BundesbankError::UnsupportedIbanRule(String::from("000503")))
+        );
+        assert_eq!(directory.to_iban("99999999", "1"), Err(BundesbankError::UnknownBank(String::from("99999999"))));
+        assert_eq!(
+            directory.to_iban("10070000", "1234586000"),
+            Err(BundesbankError::NoIbanForAccount(String::from("1234586000")))
+        );
+        assert_eq!(
+            directory.to_iban("10070000", "1234566000"),
+            Err(BundesbankError::NoIbanForAccount(String::from("1234566000")))
+        );
+        assert!(directory.to_iban("10070000", "1234570000").unwrap().ends_with("100700001234570000"));
+        assert_eq!(
+            directory.to_iban("51010400", "6161604670"),
+            Err(BundesbankError::NoIbanForAccount(String::from("6161604670")))
+        );
+        assert!(directory.to_iban("51010400", "6161604671").is_ok());
+        assert!(directory.to_iban("10050000", "1111").unwrap().ends_with("100500006600012020"));
+        assert_eq!(directory.to_iban("1234", "1"), Err(BundesbankError::InvalidBankCode(String::from("1234"))));
+    }
+}
@@ -0,0 +1,824 @@
+// Command line interface. When the app is started with arguments it runs one of these
+// modes and exits instead of opening the window.
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::bundesbank;
+use crate::credit_transfer::{self, CreditTransferOptions, Pain001Version};
+use crate::creditor_id::get_creditor_id_vo_from_str;
+use crate::dir_scan::{self, ScanOptions};
+use crate::domestic_account::get_domestic_account_vo_from_str;
+use crate::domestic_notation;
+use crate::generate::{self, GenerateOptions};
+use crate::iso20022;
+use crate::{get_iban_vo_with, BankFiles};
+use crate::lei::get_lei_vo_from_str;
+use crate::pseudonymize;
+use crate::redact::{self, RedactedFile, RedactionMode};
+use crate::rf_reference::get_rf_reference_vo_from_str;
+use crate::swift_mt;
+use crate::vocalink;
+
+const USAGE: &str = "Usage: iban-validator <mode> [VALUE]...
+       iban-validator scan <DIRECTORY> [--include GLOB]... [--exclude GLOB]...
+       iban-validator redact <FILE>... [--token] [--mapping MAPPING_FILE [--plain-mapping]]
+       iban-validator restore <FILE> --mapping MAPPING_FILE
+       iban-validator pseudonymize [--keep-bank] [--check-accounts] [IBAN]...
+       iban-validator generate <COUNTRY> [--count N] [--bank BANK_CODE] [--national] [--seed N]
+       iban-validator blz-to-iban <BLZ> <ACCOUNT_NUMBER>
+       iban-validator domestic-to-iban <COUNTRY> <ACCOUNT_NUMBER>
+       iban-validator iban-to-domestic <IBAN>
+       iban-validator payment-xml <FILE>...
+       iban-validator swift-mt <FILE>...
+       iban-validator credit-transfer <CSV_FILE> --debtor-name NAME --debtor-iban IBAN --date YYYY-MM-DD
+                      [--debtor-bic BIC] [--version 03|09] [--message-id ID]
+
+Validates the values, or the comma or newline separated values on standard input,
+and prints the results as JSON. Exits with 1 if any value is invalid.
+
+Modes:
+  iban           Validate IBANs
+  creditor-id    Validate SEPA Creditor Identifiers
+  lei            Validate Legal Entity Identifiers
+  rf             Validate ISO 11649 RF creditor references
+  us-routing     Validate US ABA routing numbers
+  clabe          Validate Mexican CLABEs
+  ca-transit     Validate Canadian transit and institution numbers, 12345-003 or 000312345
+  uk-account     Check UK sort codes and account numbers, e.g. \"20-00-00 31926819\"
+
+Scan searches the files in the directory and its subdirectories for valid IBANs,
+and prints the file, line and byte offset of each as JSON. The globs are matched
+against the paths relative to the directory.
+
+Redact writes a copy of each file, e.g. export.redacted.csv, with every valid IBAN
+masked, or with --token replaced by a token that is the same for every occurrence
+of the IBAN. The tokens are keyed with IBAN_REDACTION_KEY, which --token needs. The mapping
+file records the replacements, encrypted with IBAN_MAPPING_PASSPHRASE. Without the
+passphrase it is only written, with the IBANs in plain text, when --plain-mapping is
+given. Restore prints a redacted file with the original IBANs put back.
+
+Pseudonymize prints a valid IBAN of the same country and format for each IBAN, with
+correct national check digits, and with the bank identifier kept with --keep-bank.
+The same IBAN always gets the same pseudonym for the key in IBAN_PSEUDONYM_KEY.
+With --check-accounts, German and UK pseudonyms also pass the method of their bank in
+the files of IBAN_BLZ_FILE and IBAN_VALACDOS_FILE, and then depend on the files.
+
+Generate prints random valid IBANs of the country, one per line, by default one and
+at most 100000.
+With --bank every IBAN has the bank code, with --national the national check digits
+are correct too, and with --seed the same IBANs are printed every time.
+
+With the bank code file of the Bundesbank in IBAN_BLZ_FILE, the account numbers in
+German IBANs are checked with the method of their bank, and blz-to-iban prints the
+IBAN of a German bank code and account number.
+
+Domestic-to-iban prints the IBAN of an account number in the notation of its country,
+a French RIB, a Spanish CCC, an Italian account with CIN, a Belgian 3-7-2 or a Dutch
+bank code and account number. Iban-to-domestic prints the domestic notation of an IBAN.
+
+Payment-xml validates the IBANs and BICs in ISO 20022 credit transfers (pain.001),
+statements (camt.053) and notifications (camt.054), and prints them with the path
+of their element and the result of every transaction. Exits with 1 if any is invalid.
+
+Swift-mt validates the accounts in MT940 statements and MT103 payments, in :25:, :59:
+and :57A:, and prints them per message. IBANs and BICs are validated, German bank
+code/account numbers with IBAN_BLZ_FILE and //FW routing numbers are checked, and
+other domestic account numbers are printed unchecked. Exits with 1 if any is invalid.
+
+Credit-transfer prints a SEPA credit transfer file (pain.001.001.03, or .09 with
+--version 09) for a CSV with IBAN, name and amount columns, and optionally BIC and
+remittance columns. Rows with an invalid IBAN, BIC, name or amount are left out and
+printed on standard error, and then it exits with 1.
+
+With the modulus file of Vocalink in IBAN_VALACDOS_FILE, and its substitution table
+in IBAN_SCSUBTAB_FILE, the account numbers in UK IBANs are checked with the modulus
+check of their sort code. uk-account needs the modulus file.";
+
+// The modes that validate values, and the subcommands
+const VALUE_MODES: [&str; 8] = ["iban", "creditor-id", "lei", "rf", "us-routing", "clabe", "ca-transit", "uk-account"];
+const SUBCOMMANDS: [&str; 11] = [
+    "scan",
+    "redact",
+    "restore",
+    "pseudonymize",
+    "generate",
+    "blz-to-iban",
+    "domestic-to-iban",
+    "iban-to-domestic",
+    "payment-xml",
+    "swift-mt",
+    "credit-transfer",
+];
+
+// Whether the argument starts the command line interface. Other arguments, e.g. the -psn_ argument
+// macOS adds, are left to the app.
+pub fn is_mode(arg: &str) -> bool {
+    arg == "-h" || arg == "--help" || VALUE_MODES.contains(&arg) || SUBCOMMANDS.contains(&arg)
+}
+
+// Exit codes
+const VALID: i32 = 0;
+const INVALID: i32 = 1;
+const USAGE_ERROR: i32 = 2;
+
+fn split_values(input: &str) -> Vec<String> {
+    input
+        .split([',', '\n'])
+        .map(|value| value.split_whitespace().collect::<String>())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+// Returns the JSON output and whether every value was valid, or `None` for an unknown mode
+fn validate_values(mode: &str, values: &[String]) -> Option<(String, bool)> {
+    let (json, all_valid) = match mode {
+        "iban" => {
+            let bank_files = BankFiles::loaded();
+            let results: Vec<_> = values.iter().map(|v| get_iban_vo_with(v, &bank_files)).collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid()),
+            )
+        }
+        "creditor-id" => {
+            let results: Vec<_> = values.iter().map(|v| get_creditor_id_vo_from_str(v)).collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid()),
+            )
+        }
+        "lei" => {
+            let results: Vec<_> = values.iter().map(|v| get_lei_vo_from_str(v)).collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid()),
+            )
+        }
+        "rf" => {
+            let results: Vec<_> = values.iter().map(|v| get_rf_reference_vo_from_str(v)).collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid()),
+            )
+        }
+        "us-routing" | "clabe" | "ca-transit" => {
+            let country_code = match mode {
+                "us-routing" => "US",
+                "clabe" => "MX",
+                _ => "CA",
+            };
+            let results: Vec<_> = values
+                .iter()
+                .map(|v| get_domestic_account_vo_from_str(country_code, v))
+                .collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid()),
+            )
+        }
+        "uk-account" => {
+            let tables = vocalink::modulus_tables().unwrap_or_default();
+            // The sort code is the first 6 digits
+            let results: Vec<_> = values
+                .iter()
+                .map(|v| {
+                    let digits: String = v.chars().filter(|c| *c != '-').collect();
+                    let split = digits.char_indices().nth(6).map_or(digits.len(), |(i, _)| i);
+                    tables.check_account(&digits[..split], &digits[split..])
+                })
+                .collect();
+            (
+                serde_json::to_string_pretty(&results),
+                results.iter().all(|r| r.is_valid != Some(false)),
+            )
+        }
+        _ => return None,
+    };
+    Some((json.unwrap_or_default(), all_valid))
+}
+
+fn parse_scan_args(args: &[String]) -> Result<(PathBuf, ScanOptions), String> {
+    let mut directory = None;
+    let mut options = ScanOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--include" | "--exclude" => {
+                let glob = args.next().ok_or(format!("{} needs a glob", arg))?;
+                if arg == "--include" {
+                    options.include.push(glob.clone());
+                } else {
+                    options.exclude.push(glob.clone());
+                }
+            }
+            _ if directory.is_none() => directory = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    Ok((directory.ok_or("scan needs a directory")?, options))
+}
+
+fn scan(args: &[String]) -> i32 {
+    let (directory, options) = match parse_scan_args(args) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            return USAGE_ERROR;
+        }
+    };
+    match dir_scan::scan_directory(&directory, &options) {
+        Ok(scan) => {
+            for skipped in &scan.skipped {
+                eprintln!("Skipping {}: {}", skipped.path.to_string_lossy(), skipped.reason);
+            }
+            println!("{}", serde_json::to_string_pretty(&scan.hits).unwrap_or_default());
+            VALID
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            USAGE_ERROR
+        }
+    }
+}
+
+fn validate_payment_files(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("payment-xml needs a file\n\n{}", USAGE);
+        return USAGE_ERROR;
+    }
+    let mut reports = Vec::new();
+    for file in args {
+        let report = std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|xml| iso20022::validate_message(&xml).map_err(|e| e.to_string()));
+        match report {
+            Ok(report) => reports.push(report),
+            Err(msg) => {
+                eprintln!("{}: {}", file, msg);
+                return USAGE_ERROR;
+            }
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&reports).unwrap_or_default());
+    if reports.iter().all(|report| report.is_valid()) {
+        VALID
+    } else {
+        INVALID
+    }
+}
+
+fn scan_swift_files(args: &[String]) -> i32 {
+    if args.is_empty() {
+        eprintln!("swift-mt needs a file\n\n{}", USAGE);
+        return USAGE_ERROR;
+    }
+    let bank_files = BankFiles::loaded();
+    let mut files = Vec::new();
+    let mut all_valid = true;
+    for file in args {
+        let messages = std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| swift_mt::scan_messages(&contents, &bank_files).map_err(|e| e.to_string()));
+        match messages {
+            Ok(messages) => {
+                all_valid &= messages.iter().all(|message| message.is_valid());
+                files.push(serde_json::json!({ "file": file, "messages": messages }));
+            }
+            Err(msg) => {
+                eprintln!("{}: {}", file, msg);
+                return USAGE_ERROR;
+            }
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&files).unwrap_or_default());
+    if all_valid {
+        VALID
+    } else {
+        INVALID
+    }
+}
+
+fn parse_credit_transfer_args(args: &[String]) -> Result<(PathBuf, CreditTransferOptions), String> {
+    let mut file = None;
+    let mut options = CreditTransferOptions {
+        version: Pain001Version::V03,
+        message_id: None,
+        debtor_name: String::new(),
+        debtor_iban: String::new(),
+        debtor_bic: None,
+        execution_date: String::new(),
+        creation_date_time: None,
+        iban_column: None,
+        bic_column: None,
+        name_column: None,
+        amount_column: None,
+        remittance_column: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debtor-name" | "--debtor-iban" | "--debtor-bic" | "--date" | "--version" | "--message-id" => {
+                let value = args.next().ok_or(format!("{} needs a value", arg))?.clone();
+                match arg.as_str() {
+                    "--debtor-name" => options.debtor_name = value,
+                    "--debtor-iban" => options.debtor_iban = value,
+                    "--debtor-bic" => options.debtor_bic = Some(value),
+                    "--date" => options.execution_date = value,
+                    "--message-id" => options.message_id = Some(value),
+                    _ => {
+                        options.version = match value.as_str() {
+                            "03" => Pain001Version::V03,
+                            "09" => Pain001Version::V09,
+                            _ => return Err(format!("{} is not version 03 or 09", value)),
+                        }
+                    }
+                }
+            }
+            _ if file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    let file = file.ok_or("credit-transfer needs a CSV file")?;
+    if options.debtor_name.is_empty() || options.debtor_iban.is_empty() || options.execution_date.is_empty() {
+        return Err(String::from("credit-transfer needs --debtor-name, --debtor-iban and --date"));
+    }
+    Ok((file, options))
+}
+
+fn create_credit_transfer(args: &[String]) -> i32 {
+    let (file, options) = match parse_credit_transfer_args(args) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            return USAGE_ERROR;
+        }
+    };
+    let result = std::fs::read_to_string(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| credit_transfer::create_credit_transfer(&contents, &options).map_err(|e| e.to_string()));
+    match result {
+        Ok(transfer) => {
+            print!("{}", transfer.xml);
+            for row in &transfer.rejected {
+                eprintln!("Line {} left out: {}", row.line, row.reason);
+            }
+            if transfer.rejected.is_empty() {
+                VALID
+            } else {
+                INVALID
+            }
+        }
+        Err(msg) => {
+            eprintln!("{}", msg);
+            USAGE_ERROR
+        }
+    }
+}
+
+fn redacted_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.redacted.{}", stem, extension.to_string_lossy()),
+        None => format!("{}.redacted", stem),
+    };
+    path.with_file_name(name)
+}
+
+struct RedactArgs {
+    files: Vec<PathBuf>,
+    mapping: Option<PathBuf>,
+    use_tokens: bool,
+    // The mapping file may hold the IBANs in plain text
+    plain_mapping: bool,
+}
+
+fn parse_redact_args(args: &[String]) -> Result<RedactArgs, String> {
+    let mut redact_args = RedactArgs { files: Vec::new(), mapping: None, use_tokens: false, plain_mapping: false };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--token" => redact_args.use_tokens = true,
+            "--plain-mapping" => redact_args.plain_mapping = true,
+            "--mapping" => redact_args.mapping = Some(PathBuf::from(args.next().ok_or("--mapping needs a file")?)),
+            _ => redact_args.files.push(PathBuf::from(arg)),
+        }
+    }
+    if redact_args.files.is_empty() {
+        return Err(String::from("no files were given"));
+    }
+    Ok(redact_args)
+}
+
+fn write_mapping(path: &Path, redacted_files: &[RedactedFile], passphrase: Option<&str>) -> Result<(), String> {
+    let mapping = serde_json::to_vec_pretty(redacted_files).map_err(|e| e.to_string())?;
+    let mapping = match passphrase {
+        Some(passphrase) => redact::encrypt_mapping(&mapping, passphrase),
+        None => mapping,
+    };
+    std::fs::write(path, mapping).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+}
+
+fn read_mapping(path: &Path, passphrase: Option<&str>) -> Result<Vec<RedactedFile>, String> {
+    let mapping = std::fs::read(path).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+    let mapping = match passphrase {
+        Some(passphrase) => redact::decrypt_mapping(&mapping, passphrase).map_err(|e| e.to_string())?,
+        None => mapping,
+    };
+    serde_json::from_slice(&mapping).map_err(|e| e.to_string())
+}
+
+// The passphrase of the mapping file is IBAN_MAPPING_PASSPHRASE
+fn redact_files(args: &[String], key: &str, passphrase: Option<&str>) -> Result<String, String> {
+    let RedactArgs { files, mapping, use_tokens, plain_mapping } = parse_redact_args(args)?;
+    // A mapping file in plain text would put every IBAN next to the redacted files again
+    if mapping.is_some() && passphrase.is_none() && !plain_mapping {
+        return Err(String::from(
+            "the mapping file would hold the IBANs in plain text, set IBAN_MAPPING_PASSPHRASE or give --plain-mapping",
+        ));
+    }
+    let mode = if use_tokens { RedactionMode::Token(key.as_bytes()) } else { RedactionMode::Mask };
+
+    let mut redacted_files = Vec::new();
+    let mut summary = Vec::new();
+    for file in files {
+        let text = std::fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.to_string_lossy(), e))?;
+        let redaction = redact::redact(&text, mode).map_err(|e| format!("{}, set IBAN_REDACTION_KEY", e))?;
+        let output = redacted_path(&file);
+        std::fs::write(&output, &redaction.text).map_err(|e| format!("{}: {}", output.to_string_lossy(), e))?;
+        summary.push(serde_json::json!({
+            "file": file.to_string_lossy(),
+            "output": output.to_string_lossy(),
+            "redacted": redaction.entries.len(),
+        }));
+        redacted_files.push(RedactedFile {
+            file: output.to_string_lossy().to_string(),
+            entries: redaction.entries,
+        });
+    }
+    if let Some(mapping) = mapping {
+        write_mapping(&mapping, &redacted_files, passphrase)?;
+    }
+    serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())
+}
+
+fn restore_file(args: &[String], passphrase: Option<&str>) -> Result<String, String> {
+    let RedactArgs { files, mapping, .. } = parse_redact_args(args)?;
+    let mapping = read_mapping(&mapping.ok_or("restore needs the --mapping file")?, passphrase)?;
+    // The restored file is printed, so only one file can be restored at a time
+    let file = match files.as_slice() {
+        [file] => file,
+        _ => return Err(String::from("restore takes one file")),
+    };
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.to_string_lossy(), e))?;
+    // Files are found by their path as it was written, or else by their name
+    let redacted_file = mapping
+        .iter()
+        .find(|redacted_file| Path::new(&redacted_file.file) == file.as_path())
+        .or_else(|| {
+            mapping
+                .iter()
+                .find(|redacted_file| Path::new(&redacted_file.file).file_name() == file.file_name())
+        })
+        .ok_or(format!("{} is not in the mapping file", file.to_string_lossy()))?;
+    Ok(redact::restore(&text, &redacted_file.entries))
+}
+
+fn pseudonymize_ibans(args: &[String]) -> Result<String, String> {
+    let keep_bank_identifier = args.iter().any(|arg| arg == "--keep-bank");
+    let bank_files = if args.iter().any(|arg| arg == "--check-accounts") { BankFiles::loaded() } else { BankFiles::default() };
+    let mut ibans: Vec<String> =
+        args.iter().filter(|arg| *arg != "--keep-bank" && *arg != "--check-accounts").cloned().collect();
+    if ibans.is_empty() {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| format!("Could not read standard input: {}", e))?;
+        ibans = split_values(&input);
+    }
+    let key = std::env::var("IBAN_PSEUDONYM_KEY").map_err(|_| "pseudonymize needs the key in IBAN_PSEUDONYM_KEY")?;
+    let pseudonyms = ibans
+        .iter()
+        .map(|iban| {
+            let pseudonym = pseudonymize::pseudonymize_with(iban, key.as_bytes(), keep_bank_identifier, &bank_files)
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::json!({ "iban": iban, "pseudonym": pseudonym }))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    serde_json::to_string_pretty(&pseudonyms).map_err(|e| e.to_string())
+}
+
+fn parse_generate_args(args: &[String]) -> Result<(GenerateOptions, usize), String> {
+    let mut country_code = None;
+    let mut options = GenerateOptions {
+        country_code: String::new(),
+        bank_code: None,
+        national_check_digits: false,
+        seed: None,
+    };
+    let mut count = 1;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--count" | "--bank" | "--seed" => {
+                let value = args.next().ok_or(format!("{} needs a value", arg))?;
+                match arg.as_str() {
+                    "--count" => count = value.parse().map_err(|_| format!("{} is not a number", value))?,
+                    "--bank" => options.bank_code = Some(value.clone()),
+                    _ => options.seed = Some(value.parse().map_err(|_| format!("{} is not a number", value))?),
+                }
+            }
+            "--national" => options.national_check_digits = true,
+            _ if country_code.is_none() => country_code = Some(arg.clone()),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    options.country_code = country_code.ok_or("generate needs a country code")?;
+    Ok((options, count))
+}
+
+fn generate_ibans(args: &[String]) -> Result<String, String> {
+    let (options, count) = parse_generate_args(args)?;
+    let ibans = generate::generate_ibans(&options, count).map_err(|e| e.to_string())?;
+    Ok(ibans.join("\n"))
+}
+
+fn blz_to_iban(args: &[String]) -> Result<String, String> {
+    let (blz, account_number) = match args {
+        [blz, account_number] => (blz, account_number),
+        _ => return Err(String::from("blz-to-iban needs a bank code and an account number")),
+    };
+    bundesbank::bank_directory()
+        .and_then(|directory| directory.to_iban(blz, account_number))
+        .map_err(|e| e.to_string())
+}
+
+fn domestic_to_iban(args: &[String]) -> Result<String, String> {
+    match args {
+        [country_code, account] => domestic_notation::to_iban(country_code, account).map_err(|e| e.to_string()),
+        _ => Err(String::from("domestic-to-iban needs a country code and an account number")),
+    }
+}
+
+fn iban_to_domestic(args: &[String]) -> Result<String, String> {
+    match args {
+        [iban] => domestic_notation::to_domestic(iban).map_err(|e| e.to_string()),
+        _ => Err(String::from("iban-to-domestic needs an IBAN")),
+    }
+}
+
+// Runs a command that prints its output, errors are printed with the usage
+fn print_result(result: Result<String, String>) -> i32 {
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            VALID
+        }
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            USAGE_ERROR
+        }
+    }
+}
+
+pub fn run(args: &[String]) -> i32 {
+    let mode = args[0].as_str();
+    if mode == "-h" || mode == "--help" {
+        println!("{}", USAGE);
+        return VALID;
+    }
+    if !is_mode(mode) {
+        eprintln!("Unknown mode {}\n\n{}", mode, USAGE);
+        return USAGE_ERROR;
+    }
+    if let Ok(path) = std::env::var("IBAN_BLZ_FILE") {
+        if let Err(e) = bundesbank::load_bank_directory(Path::new(&path)) {
+            eprintln!("{}", e);
+            return USAGE_ERROR;
+        }
+    }
+    if let Ok(path) = std::env::var("IBAN_VALACDOS_FILE") {
+        if let Err(e) = vocalink::load_valacdos(Path::new(&path)) {
+            eprintln!("{}", e);
+            return USAGE_ERROR;
+        }
+    }
+    if let Ok(path) = std::env::var("IBAN_SCSUBTAB_FILE") {
+        if let Err(e) = vocalink::load_scsubtab(Path::new(&path)) {
+            eprintln!("{}", e);
+            return USAGE_ERROR;
+        }
+    }
+    if mode == "uk-account" {
+        if let Err(e) = vocalink::modulus_tables() {
+            eprintln!("{}", e);
+            return USAGE_ERROR;
+        }
+    }
+    match mode {
+        "scan" => return scan(&args[1..]),
+        "redact" => {
+            let key = std::env::var("IBAN_REDACTION_KEY").unwrap_or_default();
+            let passphrase = std::env::var("IBAN_MAPPING_PASSPHRASE").ok();
+            return print_result(redact_files(&args[1..], &key, passphrase.as_deref()));
+        }
+        "restore" => {
+            let passphrase = std::env::var("IBAN_MAPPING_PASSPHRASE").ok();
+            return print_result(restore_file(&args[1..], passphrase.as_deref()));
+        }
+        "pseudonymize" => return print_result(pseudonymize_ibans(&args[1..])),
+        "generate" => return print_result(generate_ibans(&args[1..])),
+        "blz-to-iban" => return print_result(blz_to_iban(&args[1..])),
+        "domestic-to-iban" => return print_result(domestic_to_iban(&args[1..])),
+        "iban-to-domestic" => return print_result(iban_to_domestic(&args[1..])),
+        "payment-xml" => return validate_payment_files(&args[1..]),
+        "swift-mt" => return scan_swift_files(&args[1..]),
+        "credit-transfer" => return create_credit_transfer(&args[1..]),
+        _ => {}
+    }
+
+    let mut values: Vec<String> = args[1..]
+        .iter()
+        .map(|value| value.split_whitespace().collect())
+        .collect();
+    if values.is_empty() {
+        let mut input = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut input) {
+            eprintln!("Could not read standard input: {}", e);
+            return USAGE_ERROR;
+        }
+        values = split_values(&input);
+    }
+
+    match validate_values(mode, &values) {
+        Some((json, all_valid)) => {
+            println!("{}", json);
+            if all_valid {
+                VALID
+            } else {
+                INVALID
+            }
+        }
+        None => {
+            eprintln!("Unknown mode {}\n\n{}", mode, USAGE);
+            USAGE_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_split_values() {
+        assert_eq!(
+            split_values("DE89 3704 0044 0532 0130 00,\nFR72ZZZ123456\n\n"),
+            vec!["DE89370400440532013000", "FR72ZZZ123456"]
+        );
+    }
+
+    #[test]
+    fn test_validate_values() {
+        let (json, all_valid) = validate_values("iban", &[String::from("DE89370400440532013000")]).unwrap();
+        assert!(all_valid);
+        assert!(json.contains("\"isDivisibleBy97\": true"));
+
+        let (json, all_valid) = validate_values(
+            "creditor-id",
+            &[String::from("DE98ZZZ09999999999"), String::from("DE97ZZZ09999999999")],
+        )
+        .unwrap();
+        assert!(!all_valid);
+        assert!(json.contains("\"businessCode\": \"ZZZ\""));
+
+        let (json, all_valid) = validate_values("clabe", &[String::from("002010077777777771")]).unwrap();
+        assert!(all_valid);
+        assert!(json.contains("\"branchCode\": \"010\""));
+
+        let (json, all_valid) = validate_values("rf", &[String::from("RF18539007547034")]).unwrap();
+        assert!(all_valid);
+        assert!(json.contains("\"formatted\": \"RF18 5390 0754 7034\""));
+
+        // Without the modulus file the accounts aren't checked
+        let (json, all_valid) = validate_values("uk-account", &[String::from("20-00-0031926819")]).unwrap();
+        assert!(all_valid);
+        assert!(json.contains("\"bankCode\": \"200000\""));
+        assert!(json.contains("\"accountNumber\": \"31926819\""));
+
+        assert_eq!(validate_values("bic", &[]), None);
+        for mode in VALUE_MODES {
+            assert!(validate_values(mode, &[]).is_some(), "{}", mode);
+        }
+    }
+
+    #[test]
+    fn test_is_mode() {
+        assert!(is_mode("iban"));
+        assert!(is_mode("swift-mt"));
+        assert!(is_mode("--help"));
+        assert!(!is_mode("-psn_0_12345"));
+        // Unknown modes exit before reading standard input
+        assert_eq!(run(&[String::from("typo")]), USAGE_ERROR);
+    }
+
+    #[test]
+    fn test_parse_scan_args() {
+        let args: Vec<String> = ["dumps", "--include", "*.log", "--exclude", "old/**", "--include", "*.csv"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let (directory, options) = parse_scan_args(&args).unwrap();
+        assert_eq!(directory, PathBuf::from("dumps"));
+        assert_eq!(options.include, vec!["*.log", "*.csv"]);
+        assert_eq!(options.exclude, vec!["old/**"]);
+
+        assert!(parse_scan_args(&[String::from("--include")]).is_err());
+        assert!(parse_scan_args(&[]).is_err());
+        assert!(parse_scan_args(&[String::from("a"), String::from("b")]).is_err());
+    }
+
+    #[test]
+    fn test_generate_ibans() {
+        let args: Vec<String> = ["DE", "--count", "3", "--seed", "7", "--national"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let (options, count) = parse_generate_args(&args).unwrap();
+        assert_eq!((options.seed, options.national_check_digits, count), (Some(7), true, 3));
+        let output = generate_ibans(&args).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert!(output.lines().all(|iban| crate::get_iban_vo_from_str(iban).is_valid()));
+
+        assert!(parse_generate_args(&[]).is_err());
+        assert!(parse_generate_args(&[String::from("DE"), String::from("--count")]).is_err());
+        assert!(generate_ibans(&[String::from("XX")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_credit_transfer_args() {
+        let args: Vec<String> = [
+            "batch.csv",
+            "--debtor-name",
+            "Acme",
+            "--debtor-iban",
+            "DE89370400440532013000",
+            "--date",
+            "2024-01-31",
+            "--version",
+            "09",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let (file, options) = parse_credit_transfer_args(&args).unwrap();
+        assert_eq!(file, PathBuf::from("batch.csv"));
+        assert_eq!((options.version, options.debtor_name.as_str()), (Pain001Version::V09, "Acme"));
+
+        assert!(parse_credit_transfer_args(&args[..5]).is_err());
+        assert!(parse_credit_transfer_args(&[String::from("batch.csv"), String::from("--version")]).is_err());
+    }
+
+    #[test]
+    fn test_redact_and_restore_files() {
+        let directory = std::env::temp_dir().join(format!("iban-redact-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let export = directory.join("export.csv");
+        let mapping = directory.join("mapping.json");
+        let contents = "name,iban\nAcme,DE89370400440532013000\n";
+        std::fs::write(&export, contents).unwrap();
+
+        let args: Vec<String> = [export.to_str().unwrap(), "--token", "--mapping", mapping.to_str().unwrap()]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert!(redact_files(&args, "", Some("passphrase")).unwrap_err().contains("IBAN_REDACTION_KEY"));
+        // Without a passphrase the mapping file is only written in plain text with --plain-mapping
+        assert!(redact_files(&args, "secret", None).unwrap_err().contains("--plain-mapping"));
+        let summary = redact_files(&args, "secret", Some("passphrase")).unwrap();
+        assert!(summary.contains("\"redacted\": 1"));
+
+        let redacted = directory.join("export.redacted.csv");
+        let redacted_text = std::fs::read_to_string(&redacted).unwrap();
+        let encrypted_mapping = std::fs::read(&mapping).unwrap();
+        let mut args = vec![redacted.to_str().unwrap().to_string(), String::from("--mapping"), mapping.to_str().unwrap().to_string()];
+        let restored = restore_file(&args, Some("passphrase"));
+        args.push(export.to_str().unwrap().to_string());
+        let two_files = restore_file(&args, Some("passphrase"));
+
+        let plain_args: Vec<String> = [export.to_str().unwrap(), "--mapping", mapping.to_str().unwrap(), "--plain-mapping"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let plain_summary = redact_files(&plain_args, "", None);
+        let plain_mapping = std::fs::read_to_string(&mapping).unwrap_or_default();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(!redacted_text.contains("DE89"));
+        assert!(!String::from_utf8_lossy(&encrypted_mapping).contains("DE89"));
+        assert_eq!(restored, Ok(String::from(contents)));
+        assert!(plain_summary.is_ok());
+        assert!(plain_mapping.contains("DE89370400440532013000"));
+        assert_eq!(two_files, Err(String::from("restore takes one file")));
+    }
+}
@@ -1,6 +1,31 @@
 use std::collections::HashMap;
+use std::ops::Range;
+
+use serde::Serialize;
+
+// The character classes of the BBAN format notation, n = digits, a = upper case letters, c = both
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum CharacterType {
+    #[serde(rename = "digit")]
+    N,
+    #[serde(rename = "letter")]
+    A,
+    #[serde(rename = "alphanumeric")]
+    C,
+}
+
+impl CharacterType {
+    pub fn accepts(self, c: char) -> bool {
+        match self {
+            CharacterType::N => c.is_ascii_digit(),
+            CharacterType::A => c.is_ascii_uppercase(),
+            CharacterType::C => c.is_ascii_digit() || c.is_ascii_uppercase(),
+        }
+    }
+}
+
 pub fn get_country_codes_and_length() -> HashMap<&'static str, usize> {
-    let country_codes = std::collections::HashMap::from([
+    std::collections::HashMap::from([
         ("AL", 28),
         ("AD", 24),
         ("AT", 20),
@@ -108,6 +133,254 @@ pub fn get_country_codes_and_length() -> HashMap<&'static str, usize> {
         ("NE", 28),
         ("SN", 28),
         ("TG", 28)
-    ]);
-    return country_codes;
+    ])
+}
+
+// Territories without an IBAN format of their own, with the country whose format they use. Their
+// IBANs normally start with the code of that country, but some banks issue them with the code
+// of the territory.
+pub const TERRITORIES: [(&str, &str); 16] = [
+    ("GF", "FR"),
+    ("GP", "FR"),
+    ("MQ", "FR"),
+    ("RE", "FR"),
+    ("PF", "FR"),
+    ("NC", "FR"),
+    ("YT", "FR"),
+    ("PM", "FR"),
+    ("WF", "FR"),
+    ("BL", "FR"),
+    ("MF", "FR"),
+    ("TF", "FR"),
+    ("JE", "GB"),
+    ("GG", "GB"),
+    ("IM", "GB"),
+    ("AX", "FI"),
+];
+
+// The country whose IBAN format the country code uses, which is the country itself except for
+// the territories
+pub fn get_format_country(country_code: &str) -> &str {
+    TERRITORIES
+        .iter()
+        .find(|(territory, _)| *territory == country_code)
+        .map_or(country_code, |&(_, country)| country)
+}
+
+// The BBAN structure per country as (length, character type) parts, from the IBAN registry
+pub fn get_bban_structure(country_code: &str) -> Option<&'static [(usize, CharacterType)]> {
+    use CharacterType::{A, C, N};
+    match country_code {
+        "AD" => Some(&[(4, N), (4, N), (12, C)]),
+        "AE" => Some(&[(3, N), (16, N)]),
+        "AL" => Some(&[(8, N), (16, C)]),
+        "AT" => Some(&[(5, N), (11, N)]),
+        "AZ" => Some(&[(4, A), (20, C)]),
+        "BA" => Some(&[(3, N), (3, N), (8, N), (2, N)]),
+        "BE" => Some(&[(3, N), (7, N), (2, N)]),
+        "BG" => Some(&[(4, A), (4, N), (2, N), (8, C)]),
+        "BH" => Some(&[(4, A), (14, C)]),
+        "BI" => Some(&[(5, N), (5, N), (11, N), (2, N)]),
+        "BR" => Some(&[(8, N), (5, N), (10, N), (1, A), (1, C)]),
+        "BY" => Some(&[(4, C), (4, N), (16, C)]),
+        "CH" => Some(&[(5, N), (12, C)]),
+        "CR" => Some(&[(4, N), (14, N)]),
+        "CY" => Some(&[(3, N), (5, N), (16, C)]),
+        "CZ" => Some(&[(4, N), (6, N), (10, N)]),
+        "DE" => Some(&[(8, N), (10, N)]),
+        "DK" => Some(&[(4, N), (9, N), (1, N)]),
+        "DO" => Some(&[(4, C), (20, N)]),
+        "EE" => Some(&[(2, N), (2, N), (11, N), (1, N)]),
+        "EG" => Some(&[(4, N), (4, N), (17, N)]),
+        "ES" => Some(&[(4, N), (4, N), (1, N), (1, N), (10, N)]),
+        "FI" => Some(&[(3, N), (11, N)]),
+        "FO" => Some(&[(4, N), (9, N), (1, N)]),
+        "FR" => Some(&[(5, N), (5, N), (11, C), (2, N)]),
+        "GB" => Some(&[(4, A), (6, N), (8, N)]),
+        "GE" => Some(&[(2, A), (16, N)]),
+        "GI" => Some(&[(4, A), (15, C)]),
+        "GL" => Some(&[(4, N), (9, N), (1, N)]),
+        "GR" => Some(&[(3, N), (4, N), (16, C)]),
+        "GT" => Some(&[(4, C), (20, C)]),
+        "HR" => Some(&[(7, N), (10, N)]),
+        "HU" => Some(&[(3, N), (4, N), (1, N), (15, N), (1, N)]),
+        "IE" => Some(&[(4, A), (6, N), (8, N)]),
+        "IL" => Some(&[(3, N), (3, N), (13, N)]),
+        "IQ" => Some(&[(4, A), (3, N), (12, N)]),
+        "IS" => Some(&[(4, N), (2, N), (6, N), (10, N)]),
+        "IT" => Some(&[(1, A), (5, N), (5, N), (12, C)]),
+        "JO" => Some(&[(4, A), (4, N), (18, C)]),
+        "KW" => Some(&[(4, A), (22, C)]),
+        "KZ" => Some(&[(3, N), (13, C)]),
+        "LB" => Some(&[(4, N), (20, C)]),
+        "LC" => Some(&[(4, A), (24, C)]),
+        "LI" => Some(&[(5, N), (12, C)]),
+        "LT" => Some(&[(5, N), (11, N)]),
+        "LU" => Some(&[(3, N), (13, C)]),
+        "LV" => Some(&[(4, A), (13, C)]),
+        "LY" => Some(&[(3, N), (3, N), (15, N)]),
+        "MC" => Some(&[(5, N), (5, N), (11, C), (2, N)]),
+        "MD" => Some(&[(2, C), (18, C)]),
+        "ME" => Some(&[(3, N), (13, N), (2, N)]),
+        "MK" => Some(&[(3, N), (10, C), (2, N)]),
+        "MR" => Some(&[(5, N), (5, N), (11, N), (2, N)]),
+        "MT" => Some(&[(4, A), (5, N), (18, C)]),
+        "MU" => Some(&[(4, A), (2, N), (2, N), (12, N), (3, N), (3, A)]),
+        "NL" => Some(&[(4, A), (10, N)]),
+        "NO" => Some(&[(4, N), (6, N), (1, N)]),
+        "PK" => Some(&[(4, A), (16, C)]),
+        "PL" => Some(&[(8, N), (16, N)]),
+        "PS" => Some(&[(4, A), (21, C)]),
+        "PT" => Some(&[(4, N), (4, N), (11, N), (2, N)]),
+        "QA" => Some(&[(4, A), (21, C)]),
+        "RO" => Some(&[(4, A), (16, C)]),
+        "RS" => Some(&[(3, N), (13, N), (2, N)]),
+        "SA" => Some(&[(2, N), (18, C)]),
+        "SC" => Some(&[(4, A), (2, N), (2, N), (16, N), (3, A)]),
+        "SD" => Some(&[(2, N), (12, N)]),
+        "SE" => Some(&[(3, N), (16, N), (1, N)]),
+        "SI" => Some(&[(5, N), (8, N), (2, N)]),
+        "SK" => Some(&[(4, N), (6, N), (10, N)]),
+        "SM" => Some(&[(1, A), (5, N), (5, N), (12, C)]),
+        "ST" => Some(&[(4, N), (4, N), (11, N), (2, N)]),
+        "SV" => Some(&[(4, A), (20, N)]),
+        "TL" => Some(&[(3, N), (14, N), (2, N)]),
+        "TN" => Some(&[(2, N), (3, N), (13, N), (2, N)]),
+        "TR" => Some(&[(5, N), (1, N), (16, C)]),
+        "UA" => Some(&[(6, N), (19, C)]),
+        "VA" => Some(&[(3, N), (15, N)]),
+        "VG" => Some(&[(4, A), (16, N)]),
+        "XK" => Some(&[(4, N), (10, N), (2, N)]),
+        _ => None,
+    }
+}
+
+// The character type of every position of the BBAN
+pub fn get_bban_character_types(country_code: &str) -> Option<Vec<CharacterType>> {
+    get_bban_structure(country_code).map(|structure| {
+        structure
+            .iter()
+            .flat_map(|&(length, character_type)| std::iter::repeat_n(character_type, length))
+            .collect()
+    })
+}
+
+// The position of the bank identifier in the BBAN
+pub fn get_bank_identifier_range(country_code: &str) -> Option<Range<usize>> {
+    match country_code {
+        "AD" => Some(0..4),
+        "AE" => Some(0..3),
+        "AL" => Some(0..8),
+        "AT" => Some(0..5),
+        "AZ" => Some(0..4),
+        "BA" => Some(0..3),
+        "BE" => Some(0..3),
+        "BG" => Some(0..4),
+        "BH" => Some(0..4),
+        "BI" => Some(0..5),
+        "BR" => Some(0..8),
+        "BY" => Some(0..4),
+        "CH" => Some(0..5),
+        "CR" => Some(0..4),
+        "CY" => Some(0..3),
+        "CZ" => Some(0..4),
+        "DE" => Some(0..8),
+        "DK" => Some(0..4),
+        "DO" => Some(0..4),
+        "EE" => Some(0..2),
+        "EG" => Some(0..4),
+        "ES" => Some(0..4),
+        "FI" => Some(0..3),
+        "FO" => Some(0..4),
+        "FR" => Some(0..5),
+        "GB" => Some(0..4),
+        "GE" => Some(0..2),
+        "GI" => Some(0..4),
+        "GL" => Some(0..4),
+        "GR" => Some(0..3),
+        "GT" => Some(0..4),
+        "HR" => Some(0..7),
+        "HU" => Some(0..3),
+        "IE" => Some(0..4),
+        "IL" => Some(0..3),
+        "IQ" => Some(0..4),
+        "IS" => Some(0..2),
+        "IT" => Some(1..6),
+        "JO" => Some(0..4),
+        "KW" => Some(0..4),
+        "KZ" => Some(0..3),
+        "LB" => Some(0..4),
+        "LC" => Some(0..4),
+        "LI" => Some(0..5),
+        "LT" => Some(0..5),
+        "LU" => Some(0..3),
+        "LV" => Some(0..4),
+        "LY" => Some(0..3),
+        "MC" => Some(0..5),
+        "MD" => Some(0..2),
+        "ME" => Some(0..3),
+        "MK" => Some(0..3),
+        "MR" => Some(0..5),
+        "MT" => Some(0..4),
+        "MU" => Some(0..6),
+        "NL" => Some(0..4),
+        "NO" => Some(0..4),
+        "PK" => Some(0..4),
+        "PS" => Some(0..4),
+        "PT" => Some(0..4),
+        "QA" => Some(0..4),
+        "RO" => Some(0..4),
+        "RS" => Some(0..3),
+        "SA" => Some(0..2),
+        "SC" => Some(0..6),
+        "SD" => Some(0..2),
+        "SE" => Some(0..3),
+        "SI" => Some(0..5),
+        "SK" => Some(0..4),
+        "SM" => Some(1..6),
+        "ST" => Some(0..4),
+        "SV" => Some(0..4),
+        "TL" => Some(0..3),
+        "TN" => Some(0..2),
+        "TR" => Some(0..5),
+        "UA" => Some(0..6),
+        "VA" => Some(0..3),
+        "VG" => Some(0..4),
+        "XK" => Some(0..2),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_bban_structures_match_lengths() {
+        let lengths = get_country_codes_and_length();
+        for (country_code, length) in lengths.iter() {
+            if let Some(structure) = get_bban_structure(country_code) {
+                let bban_length: usize = structure.iter().map(|(part_length, _)| part_length).sum();
+                assert_eq!(bban_length + 4, *length, "{}", country_code);
+            }
+        }
+        for (country_code, _) in lengths.iter() {
+            if let (Some(structure), Some(range)) = (get_bban_structure(country_code), get_bank_identifier_range(country_code)) {
+                assert!(range.end <= structure.iter().map(|(part_length, _)| part_length).sum(), "{}", country_code);
+            }
+        }
+        assert!(CharacterType::C.accepts('Q'));
+        assert!(!CharacterType::A.accepts('q'));
+    }
+
+    #[test]
+    fn test_territories() {
+        let lengths = get_country_codes_and_length();
+        for (territory, country) in TERRITORIES {
+            assert!(!lengths.contains_key(territory), "{}", territory);
+            assert_eq!(get_format_country(territory), country);
+        }
+        assert_eq!(get_format_country("DE"), "DE");
+    }
 }
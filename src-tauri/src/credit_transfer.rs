@@ -0,0 +1,484 @@
+// SEPA credit transfer files (pain.001) from a CSV batch, e.g. a supplier list that has been
+// validated. Every row with a valid IBAN, a valid BIC if there is one, a name and an amount becomes
+// a transaction, the other rows are rejected with the reason. The transactions are in one payment
+// information block from the debtor account, with the number of transactions and the control sum
+// in it and in the group header.
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Deserialize;
+
+use crate::csv_input::{self, find_column};
+use crate::epc_qr::{is_valid_bic, parse_amount_cents};
+use crate::get_iban_vo_from_str;
+
+const MAX_ID_LENGTH: usize = 35;
+const MAX_NAME_LENGTH: usize = 70;
+const MAX_REMITTANCE_LENGTH: usize = 140;
+// The largest amount of a SEPA credit transfer, 999999999.99 euro. With the bound the control sum
+// can't overflow, that would take more than 180 million rows
+const MAX_AMOUNT_CENTS: u64 = 99_999_999_999;
+
+#[derive(PartialEq, Debug)]
+pub enum CreditTransferError {
+    Unreadable(String),
+    MissingColumn(String),
+    InvalidDebtor(String),
+    InvalidExecutionDate(String),
+    InvalidMessageId(String),
+    NoValidRows,
+}
+
+impl fmt::Display for CreditTransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreditTransferError::Unreadable(msg) => write!(f, "the CSV could not be read: {}", msg),
+            CreditTransferError::MissingColumn(column) => write!(f, "the CSV has no {} column", column),
+            CreditTransferError::InvalidDebtor(msg) => write!(f, "invalid debtor: {}", msg),
+            CreditTransferError::InvalidExecutionDate(date) => {
+                write!(f, "{} is not an execution date like 2024-01-31", date)
+            }
+            CreditTransferError::InvalidMessageId(id) => {
+                write!(f, "{} is not a message id of 1 to {} characters", id, MAX_ID_LENGTH)
+            }
+            CreditTransferError::NoValidRows => write!(f, "the CSV has no rows that can be transferred"),
+        }
+    }
+}
+
+impl Serialize for CreditTransferError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Pain001Version {
+    #[serde(rename = "pain.001.001.03")]
+    V03,
+    // The version of the SEPA rulebooks since November 2023
+    #[serde(rename = "pain.001.001.09")]
+    V09,
+}
+
+impl Pain001Version {
+    fn as_str(self) -> &'static str {
+        match self {
+            Pain001Version::V03 => "pain.001.001.03",
+            Pain001Version::V09 => "pain.001.001.09",
+        }
+    }
+
+    fn bic_element(self) -> &'static str {
+        match self {
+            Pain001Version::V03 => "BIC",
+            Pain001Version::V09 => "BICFI",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreditTransferOptions {
+    pub version: Pain001Version,
+    // By default the creation time, e.g. 20240131120000
+    pub message_id: Option<String>,
+    pub debtor_name: String,
+    pub debtor_iban: String,
+    pub debtor_bic: Option<String>,
+    // YYYY-MM-DD
+    pub execution_date: String,
+    // By default the current time in UTC, e.g. 2024-01-31T12:00:00
+    pub creation_date_time: Option<String>,
+    // The columns of the CSV, found by their headers if not given
+    pub iban_column: Option<String>,
+    pub bic_column: Option<String>,
+    pub name_column: Option<String>,
+    pub amount_column: Option<String>,
+    pub remittance_column: Option<String>,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct RejectedRow {
+    // The line in the file, counting the header as line 1
+    pub line: u64,
+    pub reason: String,
+}
+
+impl Serialize for RejectedRow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("RejectedRow", 2)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("reason", &self.reason)?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct CreditTransferFile {
+    pub xml: String,
+    pub number_of_transactions: usize,
+    // In euro, e.g. 1250.00
+    pub control_sum: String,
+    pub rejected: Vec<RejectedRow>,
+}
+
+impl Serialize for CreditTransferFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("CreditTransferFile", 4)?;
+        state.serialize_field("xml", &self.xml)?;
+        state.serialize_field("numberOfTransactions", &self.number_of_transactions)?;
+        state.serialize_field("controlSum", &self.control_sum)?;
+        state.serialize_field("rejected", &self.rejected)?;
+        state.end()
+    }
+}
+
+// The columns of the CSV, the BIC and the remittance information are optional
+#[derive(Clone, Copy)]
+struct Columns {
+    iban: usize,
+    bic: Option<usize>,
+    name: usize,
+    amount: usize,
+    remittance: Option<usize>,
+}
+
+struct Transaction {
+    name: String,
+    iban: String,
+    bic: Option<String>,
+    amount_cents: u64,
+    remittance: Option<String>,
+}
+
+fn is_bic_header(header: &str) -> bool {
+    header.contains("bic") || header.contains("swift")
+}
+
+fn is_name_header(header: &str) -> bool {
+    header.contains("name") || ["beneficiary", "creditor", "payee", "supplier"].contains(&header)
+}
+
+fn is_amount_header(header: &str) -> bool {
+    header.contains("amount")
+}
+
+fn is_remittance_header(header: &str) -> bool {
+    ["remittance", "reference", "purpose", "description"].iter().any(|name| header.contains(name))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn format_cents(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+// The date and time in UTC of seconds since 1970, with the days converted to a date of the
+// proleptic Gregorian calendar
+fn utc_date_time(seconds: u64) -> String {
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn is_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, byte)| if i == 4 || i == 7 { *byte == b'-' } else { byte.is_ascii_digit() })
+}
+
+fn cell(record: &csv::StringRecord, column: Option<usize>) -> Option<String> {
+    column
+        .and_then(|column| record.get(column))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn compact(value: &str) -> String {
+    value.split_whitespace().collect::<String>().to_uppercase()
+}
+
+// The transaction of a row, or why it is rejected
+fn transaction(record: &csv::StringRecord, columns: Columns) -> Result<Transaction, String> {
+    let iban = compact(&cell(record, Some(columns.iban)).unwrap_or_default());
+    if !get_iban_vo_from_str(&iban).is_valid() {
+        return Err(format!("{} is not a valid IBAN", iban));
+    }
+    let bic = cell(record, columns.bic).map(|bic| compact(&bic));
+    if let Some(bic) = bic.as_ref().filter(|bic| !is_valid_bic(bic)) {
+        return Err(format!("{} is not a valid BIC", bic));
+    }
+    let name = cell(record, Some(columns.name)).ok_or("the name is missing")?;
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("the name is longer than {} characters", MAX_NAME_LENGTH));
+    }
+    let amount = cell(record, Some(columns.amount)).unwrap_or_default();
+    // Amounts can be written with a decimal comma, e.g. 12,50
+    let amount_cents = parse_amount_cents(&amount.replace(',', "."))
+        .filter(|cents| *cents > 0)
+        .ok_or(format!("{} is not an amount in euro", amount))?;
+    if amount_cents > MAX_AMOUNT_CENTS {
+        return Err(format!("{} is more than the largest amount of 999999999.99 euro", amount));
+    }
+    let remittance = cell(record, columns.remittance);
+    if remittance.as_ref().is_some_and(|remittance| remittance.chars().count() > MAX_REMITTANCE_LENGTH) {
+        return Err(format!("the remittance information is longer than {} characters", MAX_REMITTANCE_LENGTH));
+    }
+    Ok(Transaction { name, iban, bic, amount_cents, remittance })
+}
+
+fn financial_institution(version: Pain001Version, bic: Option<&str>) -> String {
+    match bic {
+        Some(bic) => format!("<FinInstnId><{0}>{1}</{0}></FinInstnId>", version.bic_element(), bic),
+        None => String::from("<FinInstnId><Othr><Id>NOTPROVIDED</Id></Othr></FinInstnId>"),
+    }
+}
+
+fn write_xml(options: &CreditTransferOptions, message_id: &str, creation: &str, transactions: &[Transaction]) -> String {
+    let version = options.version;
+    let count = transactions.len();
+    let control_sum = format_cents(transactions.iter().map(|transaction| transaction.amount_cents).sum());
+    let execution_date = match version {
+        Pain001Version::V03 => options.execution_date.clone(),
+        Pain001Version::V09 => format!("<Dt>{}</Dt>", options.execution_date),
+    };
+    let debtor_bic = options.debtor_bic.as_deref().map(compact);
+    let mut xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:{version}" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <CstmrCdtTrfInitn>
+    <GrpHdr>
+      <MsgId>{message_id}</MsgId>
+      <CreDtTm>{creation}</CreDtTm>
+      <NbOfTxs>{count}</NbOfTxs>
+      <CtrlSum>{control_sum}</CtrlSum>
+      <InitgPty><Nm>{debtor}</Nm></InitgPty>
+    </GrpHdr>
+    <PmtInf>
+      <PmtInfId>{message_id}</PmtInfId>
+      <PmtMtd>TRF</PmtMtd>
+      <NbOfTxs>{count}</NbOfTxs>
+      <CtrlSum>{control_sum}</CtrlSum>
+      <PmtTpInf><SvcLvl><Cd>SEPA</Cd></SvcLvl></PmtTpInf>
+      <ReqdExctnDt>{execution_date}</ReqdExctnDt>
+      <Dbtr><Nm>{debtor}</Nm></Dbtr>
+      <DbtrAcct><Id><IBAN>{debtor_iban}</IBAN></Id></DbtrAcct>
+      <DbtrAgt>{debtor_agent}</DbtrAgt>
+      <ChrgBr>SLEV</ChrgBr>
+"#,
+        version = version.as_str(),
+        message_id = escape(message_id),
+        creation = escape(creation),
+        count = count,
+        control_sum = control_sum,
+        debtor = escape(options.debtor_name.trim()),
+        execution_date = execution_date,
+        debtor_iban = compact(&options.debtor_iban),
+        debtor_agent = financial_institution(version, debtor_bic.as_deref()),
+    );
+    for transaction in transactions {
+        xml.push_str("      <CdtTrfTxInf>\n");
+        xml.push_str("        <PmtId><EndToEndId>NOTPROVIDED</EndToEndId></PmtId>\n");
+        xml.push_str(&format!(
+            "        <Amt><InstdAmt Ccy=\"EUR\">{}</InstdAmt></Amt>\n",
+            format_cents(transaction.amount_cents)
+        ));
+        // The creditor agent is optional for SEPA transfers
+        if let Some(bic) = &transaction.bic {
+            xml.push_str(&format!("        <CdtrAgt>{}</CdtrAgt>\n", financial_institution(version, Some(bic))));
+        }
+        xml.push_str(&format!("        <Cdtr><Nm>{}</Nm></Cdtr>\n", escape(&transaction.name)));
+        xml.push_str(&format!("        <CdtrAcct><Id><IBAN>{}</IBAN></Id></CdtrAcct>\n", transaction.iban));
+        if let Some(remittance) = &transaction.remittance {
+            xml.push_str(&format!("        <RmtInf><Ustrd>{}</Ustrd></RmtInf>\n", escape(remittance)));
+        }
+        xml.push_str("      </CdtTrfTxInf>\n");
+    }
+    xml.push_str("    </PmtInf>\n  </CstmrCdtTrfInitn>\n</Document>\n");
+    xml
+}
+
+pub fn create_credit_transfer(contents: &str, options: &CreditTransferOptions) -> Result<CreditTransferFile, CreditTransferError> {
+    if !get_iban_vo_from_str(&compact(&options.debtor_iban)).is_valid() {
+        return Err(CreditTransferError::InvalidDebtor(format!("{} is not a valid IBAN", options.debtor_iban)));
+    }
+    if let Some(bic) = options.debtor_bic.as_deref().map(compact).filter(|bic| !is_valid_bic(bic)) {
+        return Err(CreditTransferError::InvalidDebtor(format!("{} is not a valid BIC", bic)));
+    }
+    let debtor_name_length = options.debtor_name.trim().chars().count();
+    if debtor_name_length == 0 || debtor_name_length > MAX_NAME_LENGTH {
+        return Err(CreditTransferError::InvalidDebtor(format!("the name must have 1 to {} characters", MAX_NAME_LENGTH)));
+    }
+    if !is_date(&options.execution_date) {
+        return Err(CreditTransferError::InvalidExecutionDate(options.execution_date.clone()));
+    }
+    let creation = match &options.creation_date_time {
+        Some(creation) => creation.clone(),
+        None => utc_date_time(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())),
+    };
+    let message_id = match &options.message_id {
+        Some(message_id) => message_id.trim().to_string(),
+        None => creation.chars().filter(|c| c.is_ascii_digit()).collect(),
+    };
+    if message_id.is_empty() || message_id.chars().count() > MAX_ID_LENGTH {
+        return Err(CreditTransferError::InvalidMessageId(message_id));
+    }
+
+    let mut reader = csv_input::reader(contents);
+    let headers = reader.headers().map_err(|e| CreditTransferError::Unreadable(e.to_string()))?.clone();
+    // A column that is named in the options has to exist
+    let column = |name: &Option<String>, matcher: fn(&str) -> bool| {
+        find_column(&headers, name.as_deref(), matcher)
+            .map_err(|_| CreditTransferError::MissingColumn(name.clone().unwrap_or_default()))
+    };
+    let required = |column: Option<usize>, name: &str| column.ok_or_else(|| CreditTransferError::MissingColumn(name.to_string()));
+    let columns = Columns {
+        iban: required(column(&options.iban_column, |header| header.contains("iban"))?, "IBAN")?,
+        bic: column(&options.bic_column, is_bic_header)?,
+        name: required(column(&options.name_column, is_name_header)?, "name")?,
+        amount: required(column(&options.amount_column, is_amount_header)?, "amount")?,
+        remittance: column(&options.remittance_column, is_remittance_header)?,
+    };
+
+    let mut transactions = Vec::new();
+    let mut rejected = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| CreditTransferError::Unreadable(e.to_string()))?;
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        match transaction(&record, columns) {
+            Ok(transaction) => transactions.push(transaction),
+            Err(reason) => rejected.push(RejectedRow {
+                line: record.position().map_or(0, |position| position.line()),
+                reason,
+            }),
+        }
+    }
+    if transactions.is_empty() {
+        return Err(CreditTransferError::NoValidRows);
+    }
+    Ok(CreditTransferFile {
+        xml: write_xml(options, &message_id, &creation, &transactions),
+        number_of_transactions: transactions.len(),
+        control_sum: format_cents(transactions.iter().map(|transaction| transaction.amount_cents).sum()),
+        rejected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::iso20022;
+
+    const BATCH: &str = "Supplier name;IBAN;BIC;Amount;Invoice reference\n\
+                         Acme & Sons;DE89 3704 0044 0532 0130 00;COBADEFFXXX;1250,00;Invoice 1\n\
+                         Globex;DE89370400440532013001;;10.00;Invoice 2\n\
+                         Initech;FR1420041010050500013M02606;;99.5;\n\
+                         Umbrella;NL91ABNA0417164300;ABNA-NL2A;5;Invoice 4\n\
+                         ;BE68539007547034;;5;\n\
+                         Hooli;BE68539007547034;;-5;\n\
+                         Massive;BE68539007547034;;1000000000;\n";
+
+    fn options(version: Pain001Version) -> CreditTransferOptions {
+        CreditTransferOptions {
+            version,
+            message_id: Some(String::from("BATCH-1")),
+            debtor_name: String::from("Example GmbH"),
+            debtor_iban: String::from("DE02120300000000202051"),
+            debtor_bic: Some(String::from("BYLADEM1001")),
+            execution_date: String::from("2024-01-31"),
+            creation_date_time: Some(String::from("2024-01-30T12:00:00")),
+            iban_column: None,
+            bic_column: None,
+            name_column: None,
+            amount_column: None,
+            remittance_column: None,
+        }
+    }
+
+    #[test]
+    fn test_create_credit_transfer() {
+        let file = create_credit_transfer(BATCH, &options(Pain001Version::V03)).unwrap();
+        assert_eq!(file.number_of_transactions, 2);
+        assert_eq!(file.control_sum, "1349.50");
+        let lines: Vec<u64> = file.rejected.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [3, 5, 6, 7, 8]);
+        assert_eq!(file.rejected[0].reason, "DE89370400440532013001 is not a valid IBAN");
+        assert_eq!(file.rejected[4].reason, "1000000000 is more than the largest amount of 999999999.99 euro");
+
+        assert!(file.xml.contains("<NbOfTxs>2</NbOfTxs>"));
+        assert!(file.xml.contains("<CtrlSum>1349.50</CtrlSum>"));
+        assert!(file.xml.contains("<Cdtr><Nm>Acme &amp; Sons</Nm></Cdtr>"));
+        assert!(file.xml.contains("<ReqdExctnDt>2024-01-31</ReqdExctnDt>"));
+        assert!(file.xml.contains("<InstdAmt Ccy=\"EUR\">99.50</InstdAmt>"));
+        let report = iso20022::validate_message(&file.xml).unwrap();
+        assert_eq!(report.message_type, "pain.001.001.03");
+        assert_eq!(report.transactions.len(), 2);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_version_09() {
+        let file = create_credit_transfer(BATCH, &options(Pain001Version::V09)).unwrap();
+        assert!(file.xml.contains("<ReqdExctnDt><Dt>2024-01-31</Dt></ReqdExctnDt>"));
+        assert!(file.xml.contains("<BICFI>COBADEFFXXX</BICFI>"));
+        assert_eq!(iso20022::validate_message(&file.xml).unwrap().message_type, "pain.001.001.09");
+    }
+
+    #[test]
+    fn test_invalid_batches() {
+        let options = options(Pain001Version::V03);
+        assert_eq!(
+            create_credit_transfer("IBAN;Amount\nDE89370400440532013000;1\n", &options),
+            Err(CreditTransferError::MissingColumn(String::from("name")))
+        );
+        assert_eq!(
+            create_credit_transfer("Name;IBAN;Amount\nAcme;DE89370400440532013001;1\n", &options),
+            Err(CreditTransferError::NoValidRows)
+        );
+        let invalid_debtor = CreditTransferOptions { debtor_iban: String::from("DE00"), ..options.clone() };
+        assert!(matches!(create_credit_transfer(BATCH, &invalid_debtor), Err(CreditTransferError::InvalidDebtor(_))));
+        let invalid_date = CreditTransferOptions { execution_date: String::from("31.01.2024"), ..options };
+        assert_eq!(
+            create_credit_transfer(BATCH, &invalid_date),
+            Err(CreditTransferError::InvalidExecutionDate(String::from("31.01.2024")))
+        );
+    }
+
+    #[test]
+    fn test_utc_date_time() {
+        assert_eq!(utc_date_time(0), "1970-01-01T00:00:00");
+        assert_eq!(utc_date_time(1_709_210_096), "2024-02-29T12:34:56");
+    }
+}
@@ -0,0 +1,183 @@
+// SEPA Creditor Identifiers (CI), used by direct debit creditors. The layout is
+// country code + 2 check digits + 3 character business code + national identifier.
+// The check digits are MOD 97-10 like an IBAN, but the business code is left out of the calculation.
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::{country_data, iso7064};
+
+const MAX_LENGTH: usize = 35;
+
+// Total lengths of the identifiers for the countries that have a fixed national layout.
+// Other SEPA countries are accepted with any length up to 35 characters.
+fn creditor_id_lengths(country_code: &str) -> Option<&'static [usize]> {
+    match country_code {
+        "AT" => Some(&[18]),
+        // Either the 10 digit enterprise number or a 13 character identifier
+        "BE" => Some(&[17, 20]),
+        "DE" => Some(&[18]),
+        "ES" => Some(&[16]),
+        "FR" => Some(&[13]),
+        // Either the 11 digit VAT number or the 16 character fiscal code
+        "IT" => Some(&[18, 23]),
+        "LU" => Some(&[26]),
+        "NL" => Some(&[19]),
+        "PT" => Some(&[13]),
+        _ => None,
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct CreditorIdVO {
+    pub creditor_id: String,
+    pub is_alphanumeric: bool,
+    pub is_valid_country: bool,
+    pub is_correct_length: bool,
+    pub is_valid_checksum: bool,
+}
+
+impl CreditorIdVO {
+    pub fn is_valid(&self) -> bool {
+        self.is_alphanumeric && self.is_valid_country && self.is_correct_length && self.is_valid_checksum
+    }
+
+    pub fn business_code(&self) -> Option<&str> {
+        self.creditor_id.get(4..7)
+    }
+
+    pub fn national_identifier(&self) -> Option<&str> {
+        self.creditor_id.get(7..)
+    }
+}
+
+impl Serialize for CreditorIdVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("CreditorIdVO", 7)?;
+        state.serialize_field("creditorId", &self.creditor_id)?;
+        state.serialize_field("isAlphanumeric", &self.is_alphanumeric)?;
+        state.serialize_field("isValidCountry", &self.is_valid_country)?;
+        state.serialize_field("isCorrectLength", &self.is_correct_length)?;
+        state.serialize_field("isValidChecksum", &self.is_valid_checksum)?;
+        state.serialize_field("businessCode", &self.business_code())?;
+        state.serialize_field("nationalIdentifier", &self.national_identifier())?;
+        state.end()
+    }
+}
+
+pub fn get_creditor_id_vo_from_str(creditor_id: &str) -> CreditorIdVO {
+    let creditor_id: String = creditor_id.split_whitespace().collect::<String>().to_uppercase();
+    let mut creditor_id_vo = CreditorIdVO {
+        creditor_id: creditor_id.clone(),
+        is_alphanumeric: false,
+        is_valid_country: false,
+        is_correct_length: false,
+        is_valid_checksum: false,
+    };
+
+    if !creditor_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return creditor_id_vo;
+    }
+    creditor_id_vo.is_alphanumeric = true;
+
+    // The shortest identifiers have a national identifier of a single character
+    if creditor_id.len() < 8 {
+        return creditor_id_vo;
+    }
+
+    let country_code = &creditor_id[0..2];
+    creditor_id_vo.is_valid_country = country_data::get_country_codes_and_length().contains_key(country_code);
+    creditor_id_vo.is_correct_length = match creditor_id_lengths(country_code) {
+        Some(lengths) => lengths.contains(&creditor_id.len()),
+        None => creditor_id.len() <= MAX_LENGTH,
+    };
+
+    // Like an IBAN, but the business code in the positions 5 to 7 is skipped
+    let rearranged = creditor_id[7..].to_string() + &creditor_id[..4];
+    creditor_id_vo.is_valid_checksum = iso7064::System::Mod97_10.verify(&rearranged);
+    creditor_id_vo
+}
+
+// Computes the check digits for a national identifier, the business code defaults to "ZZZ"
+pub fn generate_creditor_id(country_code: &str, business_code: &str, national_identifier: &str) -> Option<String> {
+    let country_code = country_code.to_uppercase();
+    let national_identifier = national_identifier.to_uppercase();
+    if country_code.len() != 2
+        || business_code.len() != 3
+        || !(country_code.clone() + business_code + &national_identifier)
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric())
+    {
+        return None;
+    }
+    let check_digits = iso7064::System::Mod97_10
+        .compute(&(national_identifier.clone() + &country_code))
+        .ok()?;
+    Some(format!(
+        "{}{}{}{}",
+        country_code,
+        check_digits,
+        business_code.to_uppercase(),
+        national_identifier
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_valid_creditor_ids() {
+        for creditor_id in [
+            "DE98ZZZ09999999999",
+            "NL79ZZZ999999990000",
+            "FR72ZZZ123456",
+            "ES59ZZZX1234567L",
+            "IT66ZZZA1B2C3D4E5F6G7H8",
+            "BE69ZZZ050D000000008",
+        ] {
+            assert!(get_creditor_id_vo_from_str(creditor_id).is_valid(), "{}", creditor_id);
+        }
+    }
+
+    #[test]
+    fn test_business_code_is_ignored() {
+        let creditor_id = get_creditor_id_vo_from_str("de98 abc 09999999999");
+        assert_eq!(
+            creditor_id,
+            CreditorIdVO {
+                creditor_id: String::from("DE98ABC09999999999"),
+                is_alphanumeric: true,
+                is_valid_country: true,
+                is_correct_length: true,
+                is_valid_checksum: true,
+            }
+        );
+        assert_eq!(creditor_id.business_code(), Some("ABC"));
+        assert_eq!(creditor_id.national_identifier(), Some("09999999999"));
+    }
+
+    #[test]
+    fn test_invalid_creditor_ids() {
+        let wrong_checksum = get_creditor_id_vo_from_str("DE97ZZZ09999999999");
+        assert!(wrong_checksum.is_correct_length);
+        assert!(!wrong_checksum.is_valid_checksum);
+
+        assert!(!get_creditor_id_vo_from_str("DE98ZZZ0999999999").is_correct_length);
+        assert!(!get_creditor_id_vo_from_str("XX98ZZZ09999999999").is_valid_country);
+        assert!(!get_creditor_id_vo_from_str("DE98ZZZ-9999999999").is_alphanumeric);
+        assert!(!get_creditor_id_vo_from_str("DE98").is_valid());
+    }
+
+    #[test]
+    fn test_generate_creditor_id() {
+        assert_eq!(
+            generate_creditor_id("DE", "ZZZ", "09999999999"),
+            Some(String::from("DE98ZZZ09999999999"))
+        );
+        assert_eq!(generate_creditor_id("fr", "zzz", "123456"), Some(String::from("FR72ZZZ123456")));
+        assert_eq!(generate_creditor_id("DE", "ZZ", "09999999999"), None);
+    }
+}
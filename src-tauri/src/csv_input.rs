@@ -0,0 +1,215 @@
+// Validation of CSV exports with a header row, e.g. a supplier list with an IBAN, an LEI and an RF
+// reference column
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::lei::{get_lei_vo_from_str, LeiVO};
+use crate::rf_reference::{get_rf_reference_vo_from_str, RfReferenceVO};
+use crate::{get_iban_vo_from_str, IbanVO};
+
+#[derive(PartialEq, Debug)]
+pub enum CsvError {
+    Unreadable(String),
+    MissingColumn(String),
+    NoColumns,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Unreadable(msg) => write!(f, "the CSV could not be read: {}", msg),
+            CsvError::MissingColumn(column) => write!(f, "the CSV has no column named {}", column),
+            CsvError::NoColumns => write!(f, "the CSV has no IBAN, LEI or RF reference column"),
+        }
+    }
+}
+
+impl Serialize for CsvError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct CsvRowResult {
+    // The line in the file, counting the header as line 1
+    pub line: u64,
+    pub iban: Option<IbanVO>,
+    pub lei: Option<LeiVO>,
+    pub reference: Option<RfReferenceVO>,
+}
+
+impl Serialize for CsvRowResult {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("CsvRowResult", 4)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.serialize_field("lei", &self.lei)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.end()
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// Finds a column by its exact name if given, otherwise by the first header the matcher accepts
+pub fn find_column(
+    headers: &csv::StringRecord,
+    name: Option<&str>,
+    matcher: fn(&str) -> bool,
+) -> Result<Option<usize>, CsvError> {
+    match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+            .map(Some)
+            .ok_or_else(|| CsvError::MissingColumn(name.to_string())),
+        None => Ok(headers
+            .iter()
+            .position(|header| matcher(&normalize_header(header)))),
+    }
+}
+
+fn is_iban_header(header: &str) -> bool {
+    header.contains("iban")
+}
+
+fn is_lei_header(header: &str) -> bool {
+    header.starts_with("lei") || header.ends_with("lei")
+}
+
+// Only columns named for RF references, a plain "reference" column is usually free text
+fn is_reference_header(header: &str) -> bool {
+    header == "rf" || header.contains("rfreference") || header.contains("creditorreference")
+}
+
+// Many European spreadsheets export with semicolons, so the delimiter is taken from the header line
+pub fn reader(contents: &str) -> csv::Reader<&[u8]> {
+    let header_line = contents.lines().next().unwrap_or("");
+    let delimiter = if header_line.matches(';').count() > header_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(contents.as_bytes())
+}
+
+fn cell(record: &csv::StringRecord, column: Option<usize>) -> Option<String> {
+    column
+        .and_then(|column| record.get(column))
+        .map(|value| value.split_whitespace().collect::<String>())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn validate_csv(
+    contents: &str,
+    iban_column: Option<&str>,
+    lei_column: Option<&str>,
+    reference_column: Option<&str>,
+) -> Result<Vec<CsvRowResult>, CsvError> {
+    let mut reader = reader(contents);
+    let headers = reader
+        .headers()
+        .map_err(|e| CsvError::Unreadable(e.to_string()))?
+        .clone();
+    let iban_column = find_column(&headers, iban_column, is_iban_header)?;
+    let lei_column = find_column(&headers, lei_column, is_lei_header)?;
+    let reference_column = find_column(&headers, reference_column, is_reference_header)?;
+    if iban_column.is_none() && lei_column.is_none() && reference_column.is_none() {
+        return Err(CsvError::NoColumns);
+    }
+
+    let mut results = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| CsvError::Unreadable(e.to_string()))?;
+        let iban = cell(&record, iban_column);
+        let lei = cell(&record, lei_column);
+        let reference = cell(&record, reference_column);
+        if iban.is_none() && lei.is_none() && reference.is_none() {
+            continue;
+        }
+        results.push(CsvRowResult {
+            line: record.position().map_or(0, |position| position.line()),
+            iban: iban.map(|iban| get_iban_vo_from_str(&iban)),
+            lei: lei.map(|lei| get_lei_vo_from_str(&lei)),
+            reference: reference.map(|reference| get_rf_reference_vo_from_str(&reference)),
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_validate_csv() {
+        let contents = "Name,IBAN,LEI code\n\
+                        Acme,DE89 3704 0044 0532 0130 00,5493001KJTIIGC8Y1R12\n\
+                        Globex,DE89370400440532013001,\n\
+                        ,,\n\
+                        Initech,,5493001KJTIIGC8Y1R13\n";
+        let results = validate_csv(contents, None, None, None).unwrap();
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].line, 2);
+        assert!(results[0].iban.as_ref().unwrap().is_valid());
+        assert!(results[0].lei.as_ref().unwrap().is_valid());
+
+        assert!(!results[1].iban.as_ref().unwrap().is_valid());
+        assert_eq!(results[1].lei, None);
+
+        assert_eq!(results[2].line, 5);
+        assert_eq!(results[2].iban, None);
+        assert!(!results[2].lei.as_ref().unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_named_columns_and_semicolons() {
+        let contents = "Account;Counterparty\nDE89370400440532013000;HWUPKR0MPOU8FGXBT394\n";
+        assert_eq!(validate_csv(contents, None, None, None), Err(CsvError::NoColumns));
+
+        let results = validate_csv(contents, Some("account"), Some("Counterparty"), None).unwrap();
+        assert!(results[0].iban.as_ref().unwrap().is_valid());
+        assert!(results[0].lei.as_ref().unwrap().is_valid());
+
+        assert_eq!(
+            validate_csv(contents, Some("IBAN"), None, None),
+            Err(CsvError::MissingColumn(String::from("IBAN")))
+        );
+    }
+
+    #[test]
+    fn test_reference_column() {
+        let contents = "IBAN,RF reference,Reference
+                        DE89370400440532013000,RF18 5390 0754 7034,Invoice 1
+                        DE89370400440532013000,RF19539007547034,Invoice 2
+";
+        let results = validate_csv(contents, None, None, None).unwrap();
+        assert_eq!(results[0].reference.as_ref().map(|reference| reference.reference.as_str()), Some("RF18539007547034"));
+        assert!(results[0].reference.as_ref().unwrap().is_valid());
+        assert!(!results[1].reference.as_ref().unwrap().is_valid());
+
+        let results = validate_csv("Reference
+RF18539007547034
+", None, None, Some("reference")).unwrap();
+        assert!(results[0].reference.as_ref().unwrap().is_valid());
+    }
+}
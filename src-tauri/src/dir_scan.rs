@@ -0,0 +1,275 @@
+// Scanning directories of logs and exports for IBANs, for data protection audits. The files are
+// memory mapped and scanned in parallel, and only IBANs with a correct checksum are reported, as
+// the checksum filters out the numbers that only look like IBANs.
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::text_scan;
+
+// Files are scanned in blocks that end at a line break, so a huge file isn't turned into one string
+const BLOCK_SIZE: usize = 1 << 20;
+
+#[derive(PartialEq, Debug)]
+pub enum ScanError {
+    InvalidGlob(String),
+    UnreadableDirectory(String),
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::InvalidGlob(msg) => write!(f, "invalid glob: {}", msg),
+            ScanError::UnreadableDirectory(msg) => write!(f, "the directory could not be read: {}", msg),
+        }
+    }
+}
+
+impl Serialize for ScanError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct ScanHit {
+    pub file: PathBuf,
+    // Lines count from 1, the offset is in bytes from the start of the file
+    pub line: usize,
+    pub offset: usize,
+    pub iban: String,
+}
+
+impl Serialize for ScanHit {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ScanHit", 4)?;
+        state.serialize_field("file", &self.file.to_string_lossy())?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("offset", &self.offset)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.end()
+    }
+}
+
+// A file or directory that couldn't be read, the scan goes on without it
+#[derive(PartialEq, Debug)]
+pub struct SkippedPath {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct DirectoryScan {
+    pub hits: Vec<ScanHit>,
+    pub skipped: Vec<SkippedPath>,
+}
+
+// Globs are matched against the path relative to the scanned directory. Without include globs
+// every file is scanned.
+#[derive(Default, Debug)]
+pub struct ScanOptions {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+fn glob_set(globs: &[String]) -> Result<GlobSet, ScanError> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(|e| ScanError::InvalidGlob(e.to_string()))?);
+    }
+    builder.build().map_err(|e| ScanError::InvalidGlob(e.to_string()))
+}
+
+// The parts of the bytes that are valid UTF-8 with their offsets. IBANs are ASCII, so the
+// invalid bytes in binary files simply separate the parts.
+fn utf8_parts(bytes: &[u8]) -> Vec<(usize, &str)> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        match std::str::from_utf8(&bytes[start..]) {
+            Ok(text) => {
+                parts.push((start, text));
+                break;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                if let Ok(text) = std::str::from_utf8(&bytes[start..start + valid]) {
+                    parts.push((start, text));
+                }
+                start += valid + e.error_len().unwrap_or(bytes.len() - start - valid);
+            }
+        }
+    }
+    parts
+}
+
+// Splits the text at line breaks into blocks of about BLOCK_SIZE bytes
+fn blocks(text: &str) -> Vec<(usize, &str)> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + BLOCK_SIZE).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if let Some(line_end) = text[end..].find('\n') {
+            end += line_end + 1;
+        } else {
+            end = text.len();
+        }
+        blocks.push((start, &text[start..end]));
+        start = end;
+    }
+    blocks
+}
+
+// The valid IBANs in the bytes as (offset, line, IBAN)
+pub fn scan_bytes(bytes: &[u8]) -> Vec<(usize, usize, String)> {
+    let mut found = Vec::new();
+    for (part_offset, part) in utf8_parts(bytes) {
+        for (block_offset, block) in blocks(part) {
+            for iban_match in text_scan::find_ibans(block) {
+                if iban_match.validation.is_valid() {
+                    found.push((part_offset + block_offset + iban_match.start, iban_match.validation.iban));
+                }
+            }
+        }
+    }
+
+    // Lines are counted once for the whole file, the hits are in order of their offsets
+    let (mut line, mut counted) = (1, 0);
+    found
+        .into_iter()
+        .map(|(offset, iban)| {
+            line += bytes[counted..offset].iter().filter(|&&b| b == b'\n').count();
+            counted = offset;
+            (offset, line, iban)
+        })
+        .collect()
+}
+
+fn scan_path(path: &Path) -> Result<Vec<(usize, usize, String)>, std::io::Error> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(Vec::new());
+    }
+    // Safety: the map is only read, a file that is changed while it is scanned can give wrong
+    // results but it can't be written through the map
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    Ok(scan_bytes(&map))
+}
+
+pub fn scan_directory(root: &Path, options: &ScanOptions) -> Result<DirectoryScan, ScanError> {
+    let include = glob_set(&options.include)?;
+    let exclude = glob_set(&options.exclude)?;
+    if !root.is_dir() {
+        return Err(ScanError::UnreadableDirectory(root.to_string_lossy().to_string()));
+    }
+
+    let mut skipped = Vec::new();
+    let files: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                let path = e.path().unwrap_or(root).to_path_buf();
+                skipped.push(SkippedPath { path, reason: e.to_string() });
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            (options.include.is_empty() || include.is_match(relative)) && !exclude.is_match(relative)
+        })
+        .collect();
+
+    let scanned: Vec<_> = files.par_iter().map(|path| (path, scan_path(path))).collect();
+    let mut hits = Vec::new();
+    for (path, found) in scanned {
+        match found {
+            Ok(found) => hits.extend(found.into_iter().map(|(offset, line, iban)| ScanHit {
+                file: path.clone(),
+                line,
+                offset,
+                iban,
+            })),
+            Err(e) => skipped.push(SkippedPath { path: path.clone(), reason: e.to_string() }),
+        }
+    }
+    hits.sort_by(|a, b| a.file.cmp(&b.file).then(a.offset.cmp(&b.offset)));
+    Ok(DirectoryScan { hits, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_scan_bytes() {
+        let mut bytes = b"2023-01-01 payment to DE89370400440532013000\n".to_vec();
+        bytes.extend_from_slice(&[0xff, 0xfe, b'\n']);
+        bytes.extend_from_slice(b"wrong DE89370400440532013001, ok GB29 NWBK 6016 1331 9268 19\n");
+        assert_eq!(
+            scan_bytes(&bytes),
+            vec![
+                (22, 1, String::from("DE89370400440532013000")),
+                (81, 3, String::from("GB29NWBK60161331926819")),
+            ]
+        );
+        assert_eq!(&bytes[81..85], b"GB29");
+    }
+
+    #[test]
+    fn test_blocks_end_at_line_breaks() {
+        let text = "a".repeat(BLOCK_SIZE - 2) + "\nDE89370400440532013000\n" + &"b".repeat(10);
+        let blocks = blocks(&text);
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].1.ends_with("DE89370400440532013000\n"));
+        assert_eq!(scan_bytes(text.as_bytes())[0].1, 2);
+    }
+
+    #[test]
+    fn test_scan_directory() {
+        let root = std::env::temp_dir().join(format!("iban-scan-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("logs")).unwrap();
+        std::fs::write(root.join("logs/app.log"), "x\ny DE89370400440532013000\n").unwrap();
+        std::fs::write(root.join("logs/empty.log"), "").unwrap();
+        std::fs::write(root.join("export.csv"), "name,iban\nAcme,GB29NWBK60161331926819\n").unwrap();
+
+        let scan = scan_directory(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(scan.skipped, vec![]);
+        let hits = scan.hits;
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].file, root.join("export.csv"));
+        assert_eq!((hits[1].line, hits[1].offset), (2, 4));
+
+        let options = ScanOptions {
+            include: vec![String::from("**/*.log"), String::from("*.csv")],
+            exclude: vec![String::from("logs/**")],
+        };
+        let hits = scan_directory(&root, &options).unwrap().hits;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].iban, "GB29NWBK60161331926819");
+
+        let invalid = ScanOptions { include: vec![String::from("[")], exclude: vec![] };
+        assert!(matches!(scan_directory(&root, &invalid), Err(ScanError::InvalidGlob(_))));
+        assert!(matches!(
+            scan_directory(&root, &ScanOptions::default()),
+            Err(ScanError::UnreadableDirectory(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+// Finding IBANs in documents: the text layer of PDFs, Word documents (.docx) and saved emails
+// (.eml), including the documents attached to the emails. Other files are read as plain text.
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::text_scan::{self, IbanMatch};
+
+#[derive(PartialEq, Debug)]
+pub enum DocumentError {
+    UnreadableFile(String),
+    InvalidPdf(String),
+    InvalidDocx(String),
+    InvalidEmail(String),
+    NotText,
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::UnreadableFile(msg) => write!(f, "the file could not be read: {}", msg),
+            DocumentError::InvalidPdf(msg) => write!(f, "the text of the PDF could not be read: {}", msg),
+            DocumentError::InvalidDocx(msg) => write!(f, "the Word document could not be read: {}", msg),
+            DocumentError::InvalidEmail(msg) => write!(f, "the email could not be read: {}", msg),
+            DocumentError::NotText => write!(f, "the file is not a PDF, Word document, email or text file"),
+        }
+    }
+}
+
+impl Serialize for DocumentError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct DocumentIban {
+    // Pages count from 1, only PDFs have pages
+    pub page: Option<usize>,
+    pub found: IbanMatch,
+}
+
+impl Serialize for DocumentIban {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DocumentIban", 2)?;
+        state.serialize_field("page", &self.page)?;
+        state.serialize_field("found", &self.found)?;
+        state.end()
+    }
+}
+
+// The result for one file. The attachments of an email get their own results, with the
+// file name "email.eml/attachment.pdf".
+#[derive(PartialEq, Debug)]
+pub struct DocumentScan {
+    pub file: String,
+    pub ibans: Vec<DocumentIban>,
+    pub error: Option<DocumentError>,
+}
+
+impl Serialize for DocumentScan {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DocumentScan", 3)?;
+        state.serialize_field("file", &self.file)?;
+        state.serialize_field("ibans", &self.ibans)?;
+        state.serialize_field("error", &self.error)?;
+        state.end()
+    }
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn find_in_text(text: &str, page: Option<usize>) -> Vec<DocumentIban> {
+    text_scan::find_ibans(text)
+        .into_iter()
+        .map(|found| DocumentIban { page, found })
+        .collect()
+}
+
+fn pdf_pages(bytes: &[u8]) -> Result<Vec<String>, DocumentError> {
+    // The PDF parser panics on some damaged files, which must not take the app down
+    panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem_by_pages(bytes)))
+        .map_err(|_| DocumentError::InvalidPdf(String::from("the file is damaged")))?
+        .map_err(|e| DocumentError::InvalidPdf(e.to_string()))
+}
+
+// The text of the body, headers and footers, with a line per paragraph
+fn docx_text(bytes: &[u8]) -> Result<String, DocumentError> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| DocumentError::InvalidDocx(e.to_string()))?;
+    let parts: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            *name == "word/document.xml"
+                || (name.starts_with("word/header") || name.starts_with("word/footer")) && name.ends_with(".xml")
+        })
+        .map(String::from)
+        .collect();
+    if !parts.iter().any(|name| name == "word/document.xml") {
+        return Err(DocumentError::InvalidDocx(String::from("word/document.xml is missing")));
+    }
+
+    let mut text = String::new();
+    for name in parts {
+        let mut xml = String::new();
+        archive
+            .by_name(&name)
+            .and_then(|mut part| part.read_to_string(&mut xml).map_err(zip::result::ZipError::from))
+            .map_err(|e| DocumentError::InvalidDocx(e.to_string()))?;
+        let document = roxmltree::Document::parse(&xml).map_err(|e| DocumentError::InvalidDocx(e.to_string()))?;
+        for node in document.descendants() {
+            match node.tag_name().name() {
+                "t" => text.push_str(node.text().unwrap_or("")),
+                "tab" => text.push('\t'),
+                "br" | "p" => text.push('\n'),
+                _ => {}
+            }
+        }
+    }
+    Ok(text)
+}
+
+fn scan_email(file: &str, bytes: &[u8]) -> Vec<DocumentScan> {
+    let mail = match mailparse::parse_mail(bytes) {
+        Ok(mail) => mail,
+        Err(e) => return vec![failed(file, DocumentError::InvalidEmail(e.to_string()))],
+    };
+
+    let mut ibans = Vec::new();
+    let mut attachments = Vec::new();
+    for part in mail.parts() {
+        let disposition = part.get_content_disposition();
+        let filename = disposition
+            .params
+            .get("filename")
+            .or_else(|| part.ctype.params.get("name"));
+        if let Some(filename) = filename {
+            let name = format!("{}/{}", file, filename);
+            match part.get_body_raw() {
+                Ok(body) => attachments.extend(scan_document(&name, &body)),
+                Err(e) => attachments.push(failed(&name, DocumentError::InvalidEmail(e.to_string()))),
+            }
+        } else if part.ctype.mimetype.starts_with("text/") {
+            // HTML parts are searched as they are, the tags don't stop IBANs from being found
+            if let Ok(body) = part.get_body() {
+                ibans.extend(find_in_text(&body, None));
+            }
+        }
+    }
+
+    let mut scans = vec![DocumentScan { file: file.to_string(), ibans, error: None }];
+    scans.append(&mut attachments);
+    scans
+}
+
+fn failed(file: &str, error: DocumentError) -> DocumentScan {
+    DocumentScan { file: file.to_string(), ibans: Vec::new(), error: Some(error) }
+}
+
+// Finds the IBANs in a document, the type of the document is taken from the extension of its name
+pub fn scan_document(file: &str, bytes: &[u8]) -> Vec<DocumentScan> {
+    let ibans = match extension(file).as_str() {
+        "eml" => return scan_email(file, bytes),
+        "pdf" => pdf_pages(bytes).map(|pages| {
+            pages
+                .iter()
+                .enumerate()
+                .flat_map(|(page, text)| find_in_text(text, Some(page + 1)))
+                .collect()
+        }),
+        "docx" => docx_text(bytes).map(|text| find_in_text(&text, None)),
+        _ => std::str::from_utf8(bytes)
+            .map(|text| find_in_text(text, None))
+            .map_err(|_| DocumentError::NotText),
+    };
+    match ibans {
+        Ok(ibans) => vec![DocumentScan { file: file.to_string(), ibans, error: None }],
+        Err(error) => vec![failed(file, error)],
+    }
+}
+
+pub fn scan_file(path: &Path) -> Vec<DocumentScan> {
+    let file = path.to_string_lossy();
+    match std::fs::read(path) {
+        Ok(bytes) => scan_document(&file, &bytes),
+        Err(e) => vec![failed(&file, DocumentError::UnreadableFile(e.to_string()))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Write;
+
+    use super::*;
+
+    fn docx(document: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("word/document.xml", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(document.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    const DOCUMENT: &str = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+        <w:p><w:r><w:t>Invoice 2023-17</w:t></w:r></w:p>
+        <w:p><w:r><w:t>IBAN: DE89 3704 0044 </w:t></w:r><w:r><w:t>0532 0130 00</w:t></w:r></w:p>
+        </w:body></w:document>"#;
+
+    #[test]
+    fn test_scan_docx() {
+        let scans = scan_document("invoice.DOCX", &docx(DOCUMENT));
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].error, None);
+        assert_eq!(scans[0].ibans.len(), 1);
+        assert_eq!(scans[0].ibans[0].page, None);
+        assert!(scans[0].ibans[0].found.validation.is_valid());
+        assert!(scans[0].ibans[0].found.has_iban_prefix);
+
+        assert!(matches!(
+            scan_document("invoice.docx", b"not a zip file")[0].error,
+            Some(DocumentError::InvalidDocx(_))
+        ));
+    }
+
+    #[test]
+    fn test_scan_pdf() {
+        let scans = scan_document("statement.pdf", include_bytes!("testdata/two-pages.pdf"));
+        assert_eq!(scans[0].error, None);
+        let pages: Vec<(Option<usize>, &str)> = scans[0]
+            .ibans
+            .iter()
+            .map(|iban| (iban.page, iban.found.validation.iban.as_str()))
+            .collect();
+        assert_eq!(pages, [(Some(1), "DE89370400440532013000"), (Some(2), "GB29NWBK60161331926819")]);
+    }
+
+    #[test]
+    fn test_scan_email_with_attachments() {
+        use base64::Engine;
+        let attachment = base64::engine::general_purpose::STANDARD.encode(docx(DOCUMENT));
+        let email = format!(
+            "From: supplier@example.com\r\n\
+             Subject: New bank details\r\n\
+             MIME-Version: 1.0\r\n\
+             Content-Type: multipart/mixed; boundary=\"XYZ\"\r\n\
+             \r\n\
+             --XYZ\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             \r\n\
+             Please pay to GB29 NWBK 6016 1331 9268 19 from now on.\r\n\
+             --XYZ\r\n\
+             Content-Type: application/octet-stream; name=\"invoice.docx\"\r\n\
+             Content-Disposition: attachment; filename=\"invoice.docx\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\
+             \r\n\
+             {}\r\n\
+             --XYZ--\r\n",
+            attachment
+        );
+        let scans = scan_document("mail.eml", email.as_bytes());
+        assert_eq!(scans.len(), 2);
+        assert_eq!(scans[0].file, "mail.eml");
+        assert_eq!(scans[0].ibans[0].found.validation.iban, "GB29NWBK60161331926819");
+        assert_eq!(scans[1].file, "mail.eml/invoice.docx");
+        assert!(scans[1].ibans[0].found.validation.is_valid());
+
+        // Attachments that can't be decoded have the same name
+        let broken = email.replace(&attachment, "not base64 ~~~");
+        let scans = scan_document("mail.eml", broken.as_bytes());
+        assert_eq!(scans[1].file, "mail.eml/invoice.docx");
+        assert!(matches!(scans[1].error, Some(DocumentError::InvalidEmail(_))));
+    }
+
+    #[test]
+    fn test_scan_other_files() {
+        let scans = scan_document("notes.txt", b"DE89370400440532013000");
+        assert!(scans[0].ibans[0].found.validation.is_valid());
+        assert_eq!(scan_document("image.png", &[0xff, 0xfe, 0x00, 0x9f])[0].error, Some(DocumentError::NotText));
+        assert!(matches!(
+            scan_document("invoice.pdf", b"%PDF-1.4 broken")[0].error,
+            Some(DocumentError::InvalidPdf(_))
+        ));
+        assert!(matches!(
+            scan_file(Path::new("/nonexistent/invoice.pdf"))[0].error,
+            Some(DocumentError::UnreadableFile(_))
+        ));
+    }
+}
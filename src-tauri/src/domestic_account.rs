@@ -0,0 +1,170 @@
+// Bank account identifiers of countries without IBANs: US ABA routing numbers, Mexican CLABEs
+// and Canadian institution and transit numbers.
+//
+// An ABA routing number has 9 digits with a weighted check digit (weights 3, 7, 1). A CLABE has
+// 18 digits: a 3 digit bank code, a 3 digit plaza, an 11 digit account number and a check digit
+// with the weights 3, 7, 1. Canadian numbers are written as transit-institution, 12345-003, or
+// electronically as 0, the institution and the transit, 000312345. They have no check digits.
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+pub const COUNTRIES: [&str; 3] = ["US", "MX", "CA"];
+
+#[derive(PartialEq, Debug)]
+pub struct DomesticAccountVO {
+    pub account: String,
+    pub country_code: String,
+    pub is_numeric: bool,
+    pub is_valid_country: bool,
+    pub is_correct_length: bool,
+    // `None` for Canadian numbers, which have no check digits
+    pub is_valid_checksum: Option<bool>,
+    pub bank_code: Option<String>,
+    // The plaza of a CLABE or the transit of a Canadian number
+    pub branch_code: Option<String>,
+}
+
+impl DomesticAccountVO {
+    pub fn is_valid(&self) -> bool {
+        self.is_numeric && self.is_valid_country && self.is_correct_length && self.is_valid_checksum != Some(false)
+    }
+}
+
+impl Serialize for DomesticAccountVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("DomesticAccountVO", 8)?;
+        state.serialize_field("account", &self.account)?;
+        state.serialize_field("countryCode", &self.country_code)?;
+        state.serialize_field("isNumeric", &self.is_numeric)?;
+        state.serialize_field("isValidCountry", &self.is_valid_country)?;
+        state.serialize_field("isCorrectLength", &self.is_correct_length)?;
+        state.serialize_field("isValidChecksum", &self.is_valid_checksum)?;
+        state.serialize_field("bankCode", &self.bank_code)?;
+        state.serialize_field("branchCode", &self.branch_code)?;
+        state.end()
+    }
+}
+
+fn digits(account: &str) -> Vec<u32> {
+    account.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+// The weights 3, 7, 1 repeated, every product counts modulo 10
+fn weighted_sum(digits: &[u32]) -> u32 {
+    digits
+        .iter()
+        .zip([3, 7, 1].iter().cycle())
+        .map(|(digit, weight)| digit * weight % 10)
+        .sum()
+}
+
+fn validate_aba(account_vo: &mut DomesticAccountVO) {
+    account_vo.is_correct_length = account_vo.account.len() == 9;
+    if account_vo.is_correct_length {
+        account_vo.is_valid_checksum = Some(weighted_sum(&digits(&account_vo.account)).is_multiple_of(10));
+        account_vo.bank_code = Some(account_vo.account.clone());
+    }
+}
+
+fn validate_clabe(account_vo: &mut DomesticAccountVO) {
+    account_vo.is_correct_length = account_vo.account.len() == 18;
+    if account_vo.is_correct_length {
+        let digits = digits(&account_vo.account);
+        account_vo.is_valid_checksum = Some((10 - weighted_sum(&digits[..17]) % 10) % 10 == digits[17]);
+        account_vo.bank_code = Some(account_vo.account[0..3].to_string());
+        account_vo.branch_code = Some(account_vo.account[3..6].to_string());
+    }
+}
+
+fn validate_canadian(account_vo: &mut DomesticAccountVO) {
+    let account = &account_vo.account;
+    let (institution, transit) = match account.len() {
+        8 => (&account[5..8], &account[0..5]),
+        9 if account.starts_with('0') => (&account[1..4], &account[4..9]),
+        _ => return,
+    };
+    account_vo.is_correct_length = true;
+    account_vo.bank_code = Some(institution.to_string());
+    account_vo.branch_code = Some(transit.to_string());
+}
+
+pub fn get_domestic_account_vo_from_str(country_code: &str, account: &str) -> DomesticAccountVO {
+    let country_code = country_code.trim().to_uppercase();
+    let account: String = account.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+    let mut account_vo = DomesticAccountVO {
+        is_numeric: !account.is_empty() && account.chars().all(|c| c.is_ascii_digit()),
+        is_valid_country: COUNTRIES.contains(&country_code.as_str()),
+        is_correct_length: false,
+        is_valid_checksum: None,
+        bank_code: None,
+        branch_code: None,
+        account,
+        country_code,
+    };
+    if !account_vo.is_numeric {
+        return account_vo;
+    }
+    match account_vo.country_code.as_str() {
+        "US" => validate_aba(&mut account_vo),
+        "MX" => validate_clabe(&mut account_vo),
+        "CA" => validate_canadian(&mut account_vo),
+        _ => {}
+    }
+    account_vo
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_aba_routing_number() {
+        assert!(get_domestic_account_vo_from_str("US", "021000021").is_valid());
+        assert!(get_domestic_account_vo_from_str("us", "0110 0001 5").is_valid());
+        let invalid = get_domestic_account_vo_from_str("US", "021000022");
+        assert_eq!(invalid.is_valid_checksum, Some(false));
+        assert!(!invalid.is_valid());
+        assert!(!get_domestic_account_vo_from_str("US", "02100002").is_correct_length);
+    }
+
+    #[test]
+    fn test_clabe() {
+        assert_eq!(
+            get_domestic_account_vo_from_str("MX", "002 010 07777777777 1"),
+            DomesticAccountVO {
+                account: String::from("002010077777777771"),
+                country_code: String::from("MX"),
+                is_numeric: true,
+                is_valid_country: true,
+                is_correct_length: true,
+                is_valid_checksum: Some(true),
+                bank_code: Some(String::from("002")),
+                branch_code: Some(String::from("010")),
+            }
+        );
+        assert!(get_domestic_account_vo_from_str("MX", "032180000118359719").is_valid());
+        assert!(!get_domestic_account_vo_from_str("MX", "002010077777777772").is_valid());
+    }
+
+    #[test]
+    fn test_canadian_transit_number() {
+        let cheque = get_domestic_account_vo_from_str("CA", "12345-003");
+        assert!(cheque.is_valid());
+        assert_eq!(cheque.is_valid_checksum, None);
+        assert_eq!(cheque.bank_code, Some(String::from("003")));
+        assert_eq!(cheque.branch_code, Some(String::from("12345")));
+        let electronic = get_domestic_account_vo_from_str("CA", "000312345");
+        assert_eq!((electronic.bank_code, electronic.branch_code), (cheque.bank_code, cheque.branch_code));
+        assert!(!get_domestic_account_vo_from_str("CA", "100312345").is_valid());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(!get_domestic_account_vo_from_str("DE", "021000021").is_valid_country);
+        assert!(!get_domestic_account_vo_from_str("US", "02100002A").is_numeric);
+        assert!(!get_domestic_account_vo_from_str("US", "").is_valid());
+    }
+}
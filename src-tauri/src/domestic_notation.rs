@@ -0,0 +1,181 @@
+// Account numbers in the notation of their country, e.g. the French RIB, the Spanish CCC, the
+// Italian account with its CIN, the Belgian 3-7-2 account number and the Dutch bank code with the
+// account number. The domestic notations have the same fields in the same order as the BBAN, only
+// grouped and written with separators, so an IBAN is the country code and check digits in front of
+// the fields. Territories, e.g. Guadeloupe, convert like the country of their format, and Monaco and
+// San Marino, which have a format of their own in the IBAN registry, like France and Italy.
+use std::fmt;
+
+use serde::ser::{Serialize, Serializer};
+
+use crate::{country_data, get_iban_vo_from_str, iso7064, national_check};
+
+#[derive(PartialEq, Debug)]
+pub enum NotationError {
+    UnsupportedCountry(String),
+    InvalidLength(usize),
+    InvalidCharacter(char),
+    InvalidNationalCheckDigits(String),
+    InvalidIban(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::UnsupportedCountry(country_code) => {
+                write!(f, "account numbers of {} can't be converted", country_code)
+            }
+            NotationError::InvalidLength(length) => write!(f, "the account number should have {} characters", length),
+            NotationError::InvalidCharacter(c) => write!(f, "{:?} can't be in the account number", c),
+            NotationError::InvalidNationalCheckDigits(check) => {
+                write!(f, "the check digits of the account number should be {}", check)
+            }
+            NotationError::InvalidIban(iban) => write!(f, "{} is not a valid IBAN", iban),
+        }
+    }
+}
+
+impl Serialize for NotationError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+// The lengths of the groups of the domestic notation and the separator between them
+fn notation(format_country_code: &str) -> Option<(&'static [usize], char)> {
+    match format_country_code {
+        // Bank, branch, account number and RIB key, Monaco has the same format in the IBAN registry
+        "FR" | "MC" => Some((&[5, 5, 11, 2], ' ')),
+        // Bank, branch, check digits and account number
+        "ES" => Some((&[4, 4, 2, 10], ' ')),
+        // CIN, ABI bank code, CAB branch code and account number, also in San Marino
+        "IT" | "SM" => Some((&[1, 5, 5, 12], ' ')),
+        "BE" => Some((&[3, 7, 2], '-')),
+        // Bank code and account number
+        "NL" => Some((&[4, 10], ' ')),
+        _ => None,
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '-' || c == '.' || c == '/'
+}
+
+// With separators the groups can be written without their leading zeros, e.g. a Dutch account
+// number of 9 digits
+fn bban_from_groups(domestic: &str, groups: &[usize]) -> String {
+    let parts: Vec<&str> = domestic.split(is_separator).filter(|part| !part.is_empty()).collect();
+    if parts.len() == groups.len() {
+        parts
+            .iter()
+            .zip(groups)
+            .map(|(part, &length)| format!("{:0>width$}", part, width = length))
+            .collect()
+    } else {
+        parts.concat()
+    }
+}
+
+// The IBAN of an account number in the domestic notation of the country
+pub fn to_iban(country_code: &str, domestic: &str) -> Result<String, NotationError> {
+    let country_code = country_code.trim().to_uppercase();
+    let format_country_code = country_data::get_format_country(&country_code);
+    let (groups, _) = notation(format_country_code).ok_or_else(|| NotationError::UnsupportedCountry(country_code.clone()))?;
+    let types = country_data::get_bban_character_types(format_country_code)
+        .ok_or_else(|| NotationError::UnsupportedCountry(country_code.clone()))?;
+
+    let bban = bban_from_groups(&domestic.to_uppercase(), groups);
+    if bban.chars().count() != types.len() {
+        return Err(NotationError::InvalidLength(types.len()));
+    }
+    if let Some(c) = bban.chars().zip(&types).find(|(c, character_type)| !character_type.accepts(*c)).map(|(c, _)| c) {
+        return Err(NotationError::InvalidCharacter(c));
+    }
+    if let Some((range, check)) = national_check::national_check_digits(format_country_code, &bban) {
+        if bban[range] != check {
+            return Err(NotationError::InvalidNationalCheckDigits(check));
+        }
+    }
+    let check_digits = iso7064::System::Mod97_10
+        .compute(&(bban.clone() + &country_code))
+        .map_err(|_| NotationError::InvalidLength(types.len()))?;
+    Ok(format!("{}{}{}", country_code, check_digits, bban))
+}
+
+// The account number of a valid IBAN in the domestic notation of its country
+pub fn to_domestic(iban: &str) -> Result<String, NotationError> {
+    let iban: String = iban.split_whitespace().collect::<String>().to_uppercase();
+    let iban_vo = get_iban_vo_from_str(&iban);
+    if !iban_vo.is_valid() {
+        return Err(NotationError::InvalidIban(iban));
+    }
+    let (groups, separator) =
+        notation(&iban_vo.format_country_code).ok_or(NotationError::UnsupportedCountry(iban_vo.country_code))?;
+    let mut bban = &iban[4..];
+    let mut parts = Vec::with_capacity(groups.len());
+    for &length in groups {
+        let (part, rest) = bban.split_at(length);
+        parts.push(part);
+        bban = rest;
+    }
+    Ok(parts.join(&separator.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_to_iban() {
+        assert_eq!(to_iban("FR", "20041 01005 0500013M026 06"), Ok(String::from("FR1420041010050500013M02606")));
+        assert_eq!(to_iban("es", "2100-0418-45-0200051332"), Ok(String::from("ES9121000418450200051332")));
+        assert_eq!(to_iban("IT", "X 05428 11101 123456"), Ok(String::from("IT60X0542811101000000123456")));
+        assert_eq!(to_iban("BE", "539-0075470-34"), Ok(String::from("BE68539007547034")));
+        assert_eq!(to_iban("NL", "ABNA 417164300"), Ok(String::from("NL91ABNA0417164300")));
+        assert_eq!(to_iban("NL", "abna0417164300"), Ok(String::from("NL91ABNA0417164300")));
+        // Territories use the notation of the country of their format
+        assert_eq!(
+            to_iban("GP", "20041 01005 0500013M026 06"),
+            Ok(String::from("GP1120041010050500013M02606"))
+        );
+        assert_eq!(to_iban("MC", "20041 01005 0500013M026 06"), Ok(String::from("MC9320041010050500013M02606")));
+        assert_eq!(to_iban("SM", "X 05428 11101 123456"), Ok(String::from("SM88X0542811101000000123456")));
+        assert_eq!(to_domestic("MC9320041010050500013M02606"), Ok(String::from("20041 01005 0500013M026 06")));
+    }
+
+    #[test]
+    fn test_to_iban_errors() {
+        assert_eq!(to_iban("DE", "370400440532013000"), Err(NotationError::UnsupportedCountry(String::from("DE"))));
+        assert_eq!(
+            to_iban("BE", "539-0075470-3"),
+            Err(NotationError::InvalidNationalCheckDigits(String::from("34")))
+        );
+        assert_eq!(to_iban("BE", "53900754703"), Err(NotationError::InvalidLength(12)));
+        assert_eq!(to_iban("ES", "2100 0418 4A 0200051332"), Err(NotationError::InvalidCharacter('A')));
+        assert_eq!(
+            to_iban("FR", "20041 01005 0500013M026 07"),
+            Err(NotationError::InvalidNationalCheckDigits(String::from("06")))
+        );
+    }
+
+    #[test]
+    fn test_to_domestic() {
+        assert_eq!(to_domestic("FR14 2004 1010 0505 0001 3M02 606"), Ok(String::from("20041 01005 0500013M026 06")));
+        assert_eq!(to_domestic("ES9121000418450200051332"), Ok(String::from("2100 0418 45 0200051332")));
+        assert_eq!(to_domestic("IT60X0542811101000000123456"), Ok(String::from("X 05428 11101 000000123456")));
+        assert_eq!(to_domestic("BE68539007547034"), Ok(String::from("539-0075470-34")));
+        assert_eq!(to_domestic("NL91ABNA0417164300"), Ok(String::from("ABNA 0417164300")));
+        assert_eq!(
+            to_domestic("DE89370400440532013000"),
+            Err(NotationError::UnsupportedCountry(String::from("DE")))
+        );
+        assert_eq!(to_domestic("BE68539007547035"), Err(NotationError::InvalidIban(String::from("BE68539007547035"))));
+        for iban in ["FR1420041010050500013M02606", "ES9121000418450200051332", "BE68539007547034", "NL91ABNA0417164300"] {
+            assert_eq!(to_iban(&iban[..2], &to_domestic(iban).unwrap()).as_deref(), Ok(iban));
+        }
+    }
+}
@@ -0,0 +1,490 @@
+// EPC069-12 "GiroCode" payloads for SEPA credit transfers.
+// See https://www.europeanpaymentscouncil.eu/document-library/guidance-documents/quick-response-code-guidelines-enable-data-capture-initiation
+use std::fmt;
+
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::get_iban_vo_from_str;
+
+// The total payload may not exceed 331 bytes, otherwise scanners will refuse it
+const MAX_PAYLOAD_BYTES: usize = 331;
+const MAX_NAME_LENGTH: usize = 70;
+const MAX_REFERENCE_LENGTH: usize = 35;
+const MAX_REMITTANCE_TEXT_LENGTH: usize = 140;
+const MAX_INFORMATION_LENGTH: usize = 70;
+const MAX_AMOUNT_CENTS: u64 = 99_999_999_999;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum EpcVersion {
+    // Version 001 requires a BIC
+    #[serde(rename = "001")]
+    V001,
+    // Version 002 makes the BIC optional inside the EEA
+    #[serde(rename = "002")]
+    V002,
+}
+
+impl EpcVersion {
+    fn as_str(self) -> &'static str {
+        match self {
+            EpcVersion::V001 => "001",
+            EpcVersion::V002 => "002",
+        }
+    }
+}
+
+// Only the two character sets in common use are supported, the payload is encoded in the one it names
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum CharacterSet {
+    #[serde(rename = "UTF-8")]
+    Utf8,
+    #[serde(rename = "ISO-8859-1")]
+    Iso8859_1,
+}
+
+impl CharacterSet {
+    fn code(self) -> &'static str {
+        match self {
+            CharacterSet::Utf8 => "1",
+            CharacterSet::Iso8859_1 => "2",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<CharacterSet> {
+        match code {
+            "1" => Some(CharacterSet::Utf8),
+            "2" => Some(CharacterSet::Iso8859_1),
+            _ => None,
+        }
+    }
+
+    fn can_encode(self, c: char) -> bool {
+        match self {
+            CharacterSet::Utf8 => true,
+            CharacterSet::Iso8859_1 => (c as u32) <= 0xFF,
+        }
+    }
+
+    // The characters of ISO-8859-1 are the first 256 code points, one byte each
+    fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            CharacterSet::Utf8 => text.as_bytes().to_vec(),
+            CharacterSet::Iso8859_1 => text.chars().map(|c| c as u32 as u8).collect(),
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            CharacterSet::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            CharacterSet::Iso8859_1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        }
+    }
+}
+
+// The character set named in the third line of a payload
+fn payload_character_set(payload: &[u8]) -> CharacterSet {
+    payload
+        .split(|&b| b == b'\n')
+        .nth(2)
+        .and_then(|code| std::str::from_utf8(code).ok())
+        .and_then(|code| CharacterSet::from_code(code.trim()))
+        .unwrap_or(CharacterSet::Utf8)
+}
+
+// The text of the bytes of a scanned payload, which are ISO-8859-1 when the payload says so
+pub fn decode_payload_bytes(payload: &[u8]) -> String {
+    if payload.starts_with(b"BCD") {
+        payload_character_set(payload).decode(payload)
+    } else {
+        String::from_utf8_lossy(payload).into_owned()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EpcPayment {
+    pub version: EpcVersion,
+    pub character_set: CharacterSet,
+    pub bic: Option<String>,
+    pub name: String,
+    pub iban: String,
+    // Amount in euro, e.g. "12.50"
+    pub amount: Option<String>,
+    pub purpose: Option<String>,
+    // Structured creditor reference, e.g. an RF reference. Mutually exclusive with `remittance_text`
+    pub reference: Option<String>,
+    pub remittance_text: Option<String>,
+    pub information: Option<String>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum EpcError {
+    NotAnEpcPayload,
+    UnsupportedVersion(String),
+    UnsupportedCharacterSet(String),
+    MissingBic,
+    InvalidBic(String),
+    MissingName,
+    FieldTooLong { field: &'static str, max: usize },
+    UnsupportedCharacter { field: &'static str, character: char },
+    InvalidIban(String),
+    InvalidAmount(String),
+    InvalidPurpose(String),
+    ReferenceAndText,
+    PayloadTooLong(usize),
+    Render(String),
+}
+
+impl fmt::Display for EpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpcError::NotAnEpcPayload => write!(f, "the payload is not an EPC credit transfer"),
+            EpcError::UnsupportedVersion(version) => write!(f, "version {} is not supported", version),
+            EpcError::UnsupportedCharacterSet(code) => {
+                write!(f, "character set {} is not supported", code)
+            }
+            EpcError::MissingBic => write!(f, "a BIC is required for version 001"),
+            EpcError::InvalidBic(bic) => write!(f, "{} is not a valid BIC", bic),
+            EpcError::MissingName => write!(f, "the beneficiary name is required"),
+            EpcError::FieldTooLong { field, max } => {
+                write!(f, "{} can be at most {} characters", field, max)
+            }
+            EpcError::UnsupportedCharacter { field, character } => {
+                write!(f, "{} contains the unsupported character {:?}", field, character)
+            }
+            EpcError::InvalidIban(iban) => write!(f, "{} is not a valid IBAN", iban),
+            EpcError::InvalidAmount(amount) => {
+                write!(f, "{} is not an amount between 0.01 and 999999999.99", amount)
+            }
+            EpcError::InvalidPurpose(purpose) => {
+                write!(f, "{} is not a four letter purpose code", purpose)
+            }
+            EpcError::ReferenceAndText => {
+                write!(f, "use either a structured reference or a remittance text, not both")
+            }
+            EpcError::PayloadTooLong(len) => {
+                write!(f, "the payload is {} bytes, at most {} are allowed", len, MAX_PAYLOAD_BYTES)
+            }
+            EpcError::Render(msg) => write!(f, "could not render QR code: {}", msg),
+        }
+    }
+}
+
+// Tauri sends command errors to the frontend, so they are serialized with their kind and message
+impl Serialize for EpcError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+// Parses an amount such as "12", "12.5" or "12.50" into cents
+pub fn parse_amount_cents(amount: &str) -> Option<u64> {
+    let (units, decimals) = match amount.split_once('.') {
+        Some((units, decimals)) => (units, decimals),
+        None => (amount, ""),
+    };
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if units.is_empty() || decimals.len() > 2 || !all_digits(units) || !all_digits(decimals) {
+        return None;
+    }
+    let units = units.parse::<u64>().ok()?;
+    let decimals = format!("{:0<2}", decimals).parse::<u64>().ok()?;
+    units.checked_mul(100)?.checked_add(decimals)
+}
+
+pub fn is_valid_bic(bic: &str) -> bool {
+    (bic.len() == 8 || bic.len() == 11)
+        && bic.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && bic[0..6].chars().all(|c| c.is_ascii_uppercase())
+}
+
+fn check_text(
+    field: &'static str,
+    value: &str,
+    max: usize,
+    character_set: CharacterSet,
+) -> Result<(), EpcError> {
+    if value.chars().count() > max {
+        return Err(EpcError::FieldTooLong { field, max });
+    }
+    match value
+        .chars()
+        .find(|&c| c.is_control() || !character_set.can_encode(c))
+    {
+        Some(character) => Err(EpcError::UnsupportedCharacter { field, character }),
+        None => Ok(()),
+    }
+}
+
+// Treat empty optional fields coming from the form as absent
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+impl EpcPayment {
+    pub fn to_payload(&self) -> Result<String, EpcError> {
+        let cs = self.character_set;
+
+        let bic = non_empty(&self.bic).unwrap_or("");
+        if bic.is_empty() && self.version == EpcVersion::V001 {
+            return Err(EpcError::MissingBic);
+        }
+        if !bic.is_empty() && !is_valid_bic(bic) {
+            return Err(EpcError::InvalidBic(bic.to_string()));
+        }
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(EpcError::MissingName);
+        }
+        check_text("name", name, MAX_NAME_LENGTH, cs)?;
+
+        let iban: String = self.iban.split_whitespace().collect();
+        if !get_iban_vo_from_str(&iban).is_valid() {
+            return Err(EpcError::InvalidIban(iban));
+        }
+
+        let amount = match non_empty(&self.amount) {
+            Some(amount) => match parse_amount_cents(amount) {
+                Some(cents) if cents > 0 && cents <= MAX_AMOUNT_CENTS => {
+                    format!("EUR{}.{:02}", cents / 100, cents % 100)
+                }
+                _ => return Err(EpcError::InvalidAmount(amount.to_string())),
+            },
+            None => String::new(),
+        };
+
+        let purpose = non_empty(&self.purpose).unwrap_or("");
+        if !purpose.is_empty() && (purpose.len() != 4 || !purpose.chars().all(|c| c.is_ascii_uppercase())) {
+            return Err(EpcError::InvalidPurpose(purpose.to_string()));
+        }
+
+        let reference = non_empty(&self.reference).unwrap_or("");
+        let remittance_text = non_empty(&self.remittance_text).unwrap_or("");
+        if !reference.is_empty() && !remittance_text.is_empty() {
+            return Err(EpcError::ReferenceAndText);
+        }
+        check_text("reference", reference, MAX_REFERENCE_LENGTH, cs)?;
+        check_text("remittance text", remittance_text, MAX_REMITTANCE_TEXT_LENGTH, cs)?;
+
+        let information = non_empty(&self.information).unwrap_or("");
+        check_text("information", information, MAX_INFORMATION_LENGTH, cs)?;
+
+        let fields = [
+            "BCD",
+            self.version.as_str(),
+            cs.code(),
+            "SCT",
+            bic,
+            name,
+            &iban,
+            &amount,
+            purpose,
+            reference,
+            remittance_text,
+            information,
+        ];
+        // Trailing empty fields are left out
+        let last = fields.iter().rposition(|f| !f.is_empty()).unwrap_or(0);
+        let payload = fields[..=last].join("\n");
+
+        let length = cs.encode(&payload).len();
+        if length > MAX_PAYLOAD_BYTES {
+            return Err(EpcError::PayloadTooLong(length));
+        }
+        Ok(payload)
+    }
+}
+
+// Reads a scanned payload back into its fields. The fields are not validated, so that a
+// payload with e.g. a broken IBAN can still be shown to the user together with the validation result.
+pub fn parse_payload(payload: &str) -> Result<EpcPayment, EpcError> {
+    let fields: Vec<&str> = payload.lines().map(str::trim).collect();
+    if fields.len() < 7 || fields[0] != "BCD" || fields[3] != "SCT" {
+        return Err(EpcError::NotAnEpcPayload);
+    }
+    let version = match fields[1] {
+        "001" => EpcVersion::V001,
+        "002" => EpcVersion::V002,
+        other => return Err(EpcError::UnsupportedVersion(other.to_string())),
+    };
+    let character_set = CharacterSet::from_code(fields[2])
+        .ok_or_else(|| EpcError::UnsupportedCharacterSet(fields[2].to_string()))?;
+    let optional = |i: usize| {
+        fields
+            .get(i)
+            .filter(|f| !f.is_empty())
+            .map(|f| f.to_string())
+    };
+    Ok(EpcPayment {
+        version,
+        character_set,
+        bic: optional(4),
+        name: fields[5].to_string(),
+        iban: fields[6].to_string(),
+        amount: optional(7).map(|amount| amount.trim_start_matches("EUR").to_string()),
+        purpose: optional(8),
+        reference: optional(9),
+        remittance_text: optional(10),
+        information: optional(11),
+    })
+}
+
+// Renders the payload as either SVG markup or a base64 PNG data URL the frontend can show directly
+pub fn render(payload: &str, format: ImageFormat) -> Result<String, EpcError> {
+    let bytes = payload_character_set(payload.as_bytes()).encode(payload);
+    // The EPC guidelines mandate error correction level M
+    let code = QrCode::with_error_correction_level(bytes, EcLevel::M)
+        .map_err(|e| EpcError::Render(e.to_string()))?;
+    match format {
+        ImageFormat::Svg => Ok(code
+            .render::<svg::Color<'_>>()
+            .min_dimensions(200, 200)
+            .build()),
+        ImageFormat::Png => {
+            use base64::Engine;
+            let image = code.render::<image::Luma<u8>>().min_dimensions(200, 200).build();
+            let mut png = std::io::Cursor::new(Vec::new());
+            image
+                .write_to(&mut png, image::ImageFormat::Png)
+                .map_err(|e| EpcError::Render(e.to_string()))?;
+            Ok(format!(
+                "data:image/png;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(png.into_inner())
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn payment() -> EpcPayment {
+        EpcPayment {
+            version: EpcVersion::V002,
+            character_set: CharacterSet::Utf8,
+            bic: Some(String::from("BHBLDEHHXXX")),
+            name: String::from("Franz Mustermänn"),
+            iban: String::from("DE89 3704 0044 0532 0130 00"),
+            amount: Some(String::from("12.3")),
+            purpose: Some(String::from("GDDS")),
+            reference: Some(String::from("RF18539007547034")),
+            remittance_text: None,
+            information: None,
+        }
+    }
+
+    #[test]
+    fn test_payload() {
+        assert_eq!(
+            payment().to_payload().unwrap(),
+            "BCD\n002\n1\nSCT\nBHBLDEHHXXX\nFranz Mustermänn\nDE89370400440532013000\nEUR12.30\nGDDS\nRF18539007547034"
+        );
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        let mut payment = payment();
+        payment.bic = None;
+        payment.amount = None;
+        payment.purpose = None;
+        payment.reference = Some(String::new());
+        assert_eq!(
+            payment.to_payload().unwrap(),
+            "BCD\n002\n1\nSCT\n\nFranz Mustermänn\nDE89370400440532013000"
+        );
+
+        payment.version = EpcVersion::V001;
+        assert_eq!(payment.to_payload(), Err(EpcError::MissingBic));
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let mut invalid_iban = payment();
+        invalid_iban.iban = String::from("DE89370400440532013001");
+        assert!(matches!(invalid_iban.to_payload(), Err(EpcError::InvalidIban(_))));
+
+        let mut too_long = payment();
+        too_long.name = "A".repeat(71);
+        assert_eq!(
+            too_long.to_payload(),
+            Err(EpcError::FieldTooLong { field: "name", max: 70 })
+        );
+
+        let mut latin1 = payment();
+        latin1.character_set = CharacterSet::Iso8859_1;
+        latin1.name = String::from("Łukasz");
+        assert_eq!(
+            latin1.to_payload(),
+            Err(EpcError::UnsupportedCharacter { field: "name", character: 'Ł' })
+        );
+
+        let mut both = payment();
+        both.remittance_text = Some(String::from("Invoice 123"));
+        assert_eq!(both.to_payload(), Err(EpcError::ReferenceAndText));
+
+        let mut zero = payment();
+        zero.amount = Some(String::from("0.00"));
+        assert!(matches!(zero.to_payload(), Err(EpcError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn test_parse_amount_cents() {
+        assert_eq!(parse_amount_cents("12"), Some(1200));
+        assert_eq!(parse_amount_cents("12.5"), Some(1250));
+        assert_eq!(parse_amount_cents("0.01"), Some(1));
+        assert_eq!(parse_amount_cents("12.345"), None);
+        assert_eq!(parse_amount_cents("-1"), None);
+        assert_eq!(parse_amount_cents(".5"), None);
+    }
+
+    #[test]
+    fn test_parse_payload() {
+        let payload = payment().to_payload().unwrap();
+        let mut expected = payment();
+        expected.iban = String::from("DE89370400440532013000");
+        expected.amount = Some(String::from("12.30"));
+        assert_eq!(parse_payload(&payload), Ok(expected));
+
+        assert_eq!(parse_payload("SPC\n0200\n1"), Err(EpcError::NotAnEpcPayload));
+        assert_eq!(
+            parse_payload("BCD\n003\n1\nSCT\n\nName\nDE89370400440532013000"),
+            Err(EpcError::UnsupportedVersion(String::from("003")))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let payload = payment().to_payload().unwrap();
+        assert!(render(&payload, ImageFormat::Svg).unwrap().contains("<svg"));
+        assert!(render(&payload, ImageFormat::Png)
+            .unwrap()
+            .starts_with("data:image/png;base64,"));
+    }
+
+    #[test]
+    fn test_latin1_payload() {
+        let mut latin1 = payment();
+        latin1.character_set = CharacterSet::Iso8859_1;
+        let payload = latin1.to_payload().unwrap();
+        let bytes = CharacterSet::Iso8859_1.encode(&payload);
+        // "ä" is the single byte 0xE4 instead of the two bytes of UTF-8
+        assert_eq!(bytes.len(), payload.len() - 1);
+        assert!(bytes.contains(&0xE4));
+        assert_eq!(decode_payload_bytes(&bytes), payload);
+    }
+}
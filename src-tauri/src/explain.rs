@@ -0,0 +1,216 @@
+// Step by step explanation of the IBAN check digit calculation, for showing someone why an IBAN
+// is rejected. It follows the steps in the README, and computes the remainder in chunks of nine
+// digits the way it is done by hand: the remainder of each chunk is put in front of the next digits.
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::iso7064;
+
+const FIRST_CHUNK_LENGTH: usize = 9;
+const NEXT_CHUNK_LENGTH: usize = 7;
+
+#[derive(PartialEq, Debug)]
+pub enum ExplainError {
+    TooShort,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for ExplainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplainError::TooShort => write!(f, "an IBAN has at least 5 characters"),
+            ExplainError::InvalidCharacter(c) => write!(f, "{:?} is not a letter A-Z or a digit", c),
+        }
+    }
+}
+
+impl Serialize for ExplainError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct LetterExpansion {
+    pub letter: char,
+    pub digits: String,
+}
+
+impl Serialize for LetterExpansion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("LetterExpansion", 2)?;
+        state.serialize_field("letter", &self.letter)?;
+        state.serialize_field("digits", &self.digits)?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct RemainderStep {
+    // The digits divided in this step, starting with the remainder of the previous step
+    pub digits: String,
+    pub remainder: u32,
+}
+
+impl Serialize for RemainderStep {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("RemainderStep", 2)?;
+        state.serialize_field("digits", &self.digits)?;
+        state.serialize_field("remainder", &self.remainder)?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct ChecksumExplanation {
+    pub iban: String,
+    pub rearranged: String,
+    pub letters: Vec<LetterExpansion>,
+    pub expanded: String,
+    pub steps: Vec<RemainderStep>,
+    pub remainder: u32,
+    // The check digits that would make the rest of the IBAN valid
+    pub expected_check_digits: String,
+}
+
+impl ChecksumExplanation {
+    pub fn is_divisible_by_97(&self) -> bool {
+        self.remainder == 1
+    }
+}
+
+impl Serialize for ChecksumExplanation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ChecksumExplanation", 8)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.serialize_field("rearranged", &self.rearranged)?;
+        state.serialize_field("letters", &self.letters)?;
+        state.serialize_field("expanded", &self.expanded)?;
+        state.serialize_field("steps", &self.steps)?;
+        state.serialize_field("remainder", &self.remainder)?;
+        state.serialize_field("isDivisibleBy97", &self.is_divisible_by_97())?;
+        state.serialize_field("expectedCheckDigits", &self.expected_check_digits)?;
+        state.end()
+    }
+}
+
+fn remainder_steps(expanded: &str) -> Vec<RemainderStep> {
+    let split = expanded.len().min(FIRST_CHUNK_LENGTH);
+    let (mut digits, mut rest) = (expanded[..split].to_string(), &expanded[split..]);
+    let mut steps = Vec::new();
+    loop {
+        // At most nine digits, so the chunk always fits
+        let remainder = (digits.parse::<u64>().unwrap_or(0) % 97) as u32;
+        steps.push(RemainderStep { digits, remainder });
+        if rest.is_empty() {
+            return steps;
+        }
+        let split = rest.len().min(NEXT_CHUNK_LENGTH);
+        digits = format!("{:02}{}", remainder, &rest[..split]);
+        rest = &rest[split..];
+    }
+}
+
+pub fn explain_checksum(iban: &str) -> Result<ChecksumExplanation, ExplainError> {
+    let iban: String = iban.split_whitespace().collect::<String>().to_uppercase();
+    if let Some(c) = iban.chars().find(|c| !c.is_ascii_alphanumeric()) {
+        return Err(ExplainError::InvalidCharacter(c));
+    }
+    if iban.len() < 5 {
+        return Err(ExplainError::TooShort);
+    }
+
+    // Move the first four characters to the end of the string
+    let rearranged = iban[4..].to_string() + &iban[..4];
+
+    // Replace each letter with two digits, A = 10, ..., Z = 35
+    let mut letters: Vec<LetterExpansion> = Vec::new();
+    let mut expanded = String::new();
+    for c in rearranged.chars() {
+        let digits = c.to_digit(36).unwrap_or(0).to_string();
+        if c.is_ascii_alphabetic() && !letters.iter().any(|expansion| expansion.letter == c) {
+            letters.push(LetterExpansion { letter: c, digits: digits.clone() });
+        }
+        expanded.push_str(&digits);
+    }
+
+    let steps = remainder_steps(&expanded);
+    let remainder = steps.last().map_or(0, |step| step.remainder);
+    let expected_check_digits = iso7064::System::Mod97_10
+        .compute(&(iban[4..].to_string() + &iban[..2]))
+        .unwrap_or_default();
+
+    Ok(ChecksumExplanation {
+        iban,
+        rearranged,
+        letters,
+        expanded,
+        steps,
+        remainder,
+        expected_check_digits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_explain_valid_iban() {
+        let explanation = explain_checksum("DE89 3704 0044 0532 0130 00").unwrap();
+        assert_eq!(explanation.rearranged, "370400440532013000DE89");
+        assert_eq!(
+            explanation.letters,
+            vec![
+                LetterExpansion { letter: 'D', digits: String::from("13") },
+                LetterExpansion { letter: 'E', digits: String::from("14") },
+            ]
+        );
+        assert_eq!(explanation.expanded, "370400440532013000131489");
+        assert_eq!(
+            explanation.steps,
+            vec![
+                RemainderStep { digits: String::from("370400440"), remainder: 23 },
+                RemainderStep { digits: String::from("235320130"), remainder: 70 },
+                RemainderStep { digits: String::from("700013148"), remainder: 38 },
+                RemainderStep { digits: String::from("389"), remainder: 1 },
+            ]
+        );
+        assert!(explanation.is_divisible_by_97());
+        assert_eq!(explanation.expected_check_digits, "89");
+    }
+
+    #[test]
+    fn test_explain_invalid_iban() {
+        let explanation = explain_checksum("DE89370400440532013001").unwrap();
+        assert_eq!(explanation.remainder, 28);
+        assert!(!explanation.is_divisible_by_97());
+        assert_eq!(explanation.expected_check_digits, "62");
+
+        // The remainder agrees with the calculation of the whole number
+        let long = explain_checksum("MT84MALT011000012345MTLCAST001S").unwrap();
+        assert_eq!(Ok(long.remainder), iso7064::mod97_10_remainder(&long.rearranged));
+        assert!(long.is_divisible_by_97());
+    }
+
+    #[test]
+    fn test_explain_errors() {
+        assert_eq!(explain_checksum("DE89"), Err(ExplainError::TooShort));
+        assert_eq!(explain_checksum("DE89-3704"), Err(ExplainError::InvalidCharacter('-')));
+        assert_eq!(explain_checksum("DE89Ö3704"), Err(ExplainError::InvalidCharacter('Ö')));
+    }
+}
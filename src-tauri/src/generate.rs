@@ -0,0 +1,220 @@
+// Random IBANs for test data. The BBAN follows the format of the country in the IBAN registry,
+// and the IBAN check digits are correct, so the IBANs pass validation without belonging to
+// real accounts.
+use std::fmt;
+
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use hmac::{Hmac, Mac};
+use serde::ser::{Serialize, Serializer};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::country_data::{self, CharacterType};
+use crate::{iso7064, national_check};
+
+// The most IBANs one call makes, more would only fill the memory
+pub const MAX_COUNT: usize = 100_000;
+
+#[derive(PartialEq, Debug)]
+pub enum GenerateError {
+    UnknownCountry(String),
+    NoBankIdentifier(String),
+    InvalidBankCode(String),
+    TooMany(usize),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::UnknownCountry(country_code) => write!(f, "{} is not a country with IBANs", country_code),
+            GenerateError::NoBankIdentifier(country_code) => {
+                write!(f, "the IBANs of {} have no bank identifier", country_code)
+            }
+            GenerateError::InvalidBankCode(msg) => write!(f, "invalid bank code: {}", msg),
+            GenerateError::TooMany(count) => write!(f, "{} IBANs are too many, at most {} can be generated", count, MAX_COUNT),
+        }
+    }
+}
+
+impl Serialize for GenerateError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateOptions {
+    pub country_code: String,
+    // Every IBAN gets this bank identifier, e.g. the bank code of a test bank
+    pub bank_code: Option<String>,
+    // Correct national check digits for the countries that have them, otherwise they are random
+    #[serde(default)]
+    pub national_check_digits: bool,
+    // The same seed always gives the same IBANs
+    pub seed: Option<u64>,
+}
+
+// A character of the type for a random byte
+pub fn random_char(character_type: CharacterType, byte: u8) -> char {
+    const CHARACTERS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let index = match character_type {
+        CharacterType::N => byte % 10,
+        CharacterType::A => 10 + byte % 26,
+        CharacterType::C => byte % 36,
+    };
+    CHARACTERS[index as usize] as char
+}
+
+// The HMAC of a counter keyed with the seed gives 32 random bytes at a time, enough for the
+// longest BBAN of 30 characters
+fn random_bytes(seed: u64, counter: u64) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&seed.to_be_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub fn generate_ibans(options: &GenerateOptions, count: usize) -> Result<Vec<String>, GenerateError> {
+    if count > MAX_COUNT {
+        return Err(GenerateError::TooMany(count));
+    }
+    let country_code = options.country_code.trim().to_uppercase();
+    let format_country_code = country_data::get_format_country(&country_code);
+    let types = country_data::get_bban_character_types(format_country_code)
+        .ok_or_else(|| GenerateError::UnknownCountry(country_code.clone()))?;
+
+    let bank_code = match &options.bank_code {
+        Some(bank_code) => {
+            let range = country_data::get_bank_identifier_range(format_country_code)
+                .ok_or_else(|| GenerateError::NoBankIdentifier(country_code.clone()))?;
+            let bank_code: String = bank_code.split_whitespace().collect::<String>().to_uppercase();
+            if bank_code.chars().count() != range.len() {
+                return Err(GenerateError::InvalidBankCode(format!(
+                    "the bank identifier of {} has {} characters",
+                    country_code,
+                    range.len()
+                )));
+            }
+            if let Some((position, c)) = bank_code
+                .chars()
+                .zip(&types[range.clone()])
+                .enumerate()
+                .find(|(_, (c, character_type))| !character_type.accepts(*c))
+                .map(|(position, (c, _))| (position, c))
+            {
+                return Err(GenerateError::InvalidBankCode(format!(
+                    "{} can't be at position {} of the bank identifier",
+                    c,
+                    position + 1
+                )));
+            }
+            Some((range, bank_code))
+        }
+        None => None,
+    };
+
+    let seed = options.seed.unwrap_or_else(|| OsRng.next_u64());
+    let mut ibans = Vec::with_capacity(count);
+    let mut counter = 0;
+    while ibans.len() < count {
+        let bytes = random_bytes(seed, counter);
+        counter += 1;
+        let mut bban: String = types
+            .iter()
+            .zip(bytes)
+            .map(|(&character_type, byte)| random_char(character_type, byte))
+            .collect();
+        if let Some((range, bank_code)) = &bank_code {
+            bban.replace_range(range.clone(), bank_code);
+        }
+        if options.national_check_digits && national_check::COUNTRIES.contains(&format_country_code) {
+            // Some account numbers have no check digit, e.g. in Norway, then the next one is tried
+            match national_check::national_check_digits(format_country_code, &bban) {
+                Some((range, check)) => bban.replace_range(range, &check),
+                None => continue,
+            }
+        }
+        let check_digits = iso7064::System::Mod97_10
+            .compute(&(bban.clone() + &country_code))
+            .expect("the BBAN only has digits and upper case letters");
+        ibans.push(format!("{}{}{}", country_code, check_digits, bban));
+    }
+    Ok(ibans)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::get_iban_vo_from_str;
+    use crate::strategies::countries;
+
+    fn options(country_code: &str) -> GenerateOptions {
+        GenerateOptions {
+            country_code: String::from(country_code),
+            bank_code: None,
+            national_check_digits: false,
+            seed: Some(42),
+        }
+    }
+
+    #[test]
+    fn test_generated_ibans_are_valid() {
+        for country_code in countries() {
+            let ibans = generate_ibans(&options(country_code), 20).unwrap();
+            assert_eq!(ibans.len(), 20);
+            for iban in ibans {
+                assert!(get_iban_vo_from_str(&iban).is_valid(), "{}", iban);
+                let types = country_data::get_bban_character_types(country_code).unwrap();
+                assert!(iban[4..].chars().zip(types).all(|(c, character_type)| character_type.accepts(c)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed() {
+        let ibans = generate_ibans(&options("DE"), 5).unwrap();
+        assert_eq!(ibans, generate_ibans(&options("de"), 5).unwrap());
+        assert_ne!(ibans, generate_ibans(&GenerateOptions { seed: Some(43), ..options("DE") }, 5).unwrap());
+        assert_ne!(ibans[0], ibans[1]);
+    }
+
+    #[test]
+    fn test_bank_code_and_national_check_digits() {
+        let options = GenerateOptions {
+            bank_code: Some(String::from("nwbk")),
+            ..options("GB")
+        };
+        assert!(generate_ibans(&options, 10).unwrap().iter().all(|iban| &iban[4..8] == "NWBK"));
+        let options = GenerateOptions { national_check_digits: true, ..self::options("RE") };
+        for iban in generate_ibans(&options, 10).unwrap() {
+            assert!(get_iban_vo_from_str(&iban).is_valid(), "{}", iban);
+            assert_eq!(national_check::tests::is_valid_national("FR", &iban[4..]), Some(true));
+        }
+
+        for country_code in national_check::COUNTRIES {
+            let options = GenerateOptions { national_check_digits: true, ..self::options(country_code) };
+            for iban in generate_ibans(&options, 20).unwrap() {
+                assert_eq!(
+                    national_check::tests::is_valid_national(country_code, &iban[4..]),
+                    Some(true),
+                    "{}",
+                    iban
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert_eq!(generate_ibans(&options("XX"), 1), Err(GenerateError::UnknownCountry(String::from("XX"))));
+        assert_eq!(generate_ibans(&options("DE"), MAX_COUNT + 1), Err(GenerateError::TooMany(MAX_COUNT + 1)));
+        let options = GenerateOptions { bank_code: Some(String::from("1234")), ..options("GB") };
+        assert!(matches!(generate_ibans(&options, 1), Err(GenerateError::InvalidBankCode(_))));
+        let options = GenerateOptions { bank_code: Some(String::from("123")), ..options };
+        assert!(matches!(generate_ibans(&options, 1), Err(GenerateError::InvalidBankCode(_))));
+    }
+}
@@ -0,0 +1,191 @@
+// A validated IBAN as a type. Parsing checks the characters, the country, the length, the BBAN
+// format of the country and the check digits, so the parts can be sliced without further checks.
+// The validation result for the app, with every check on its own, is the `IbanVO` of
+// `get_iban_vo_from_str`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::ser::{Serialize, Serializer};
+
+use crate::{country_data, get_iban_vo_from_str};
+
+// The range of the QR institution identifiers (QR-IID) of Swiss and Liechtenstein QR-IBANs
+const QR_IID_RANGE: std::ops::RangeInclusive<u16> = 30000..=31999;
+
+#[derive(PartialEq, Debug)]
+pub enum ParseIbanError {
+    InvalidCharacters(String),
+    UnknownCountry(String),
+    InvalidLength(String),
+    InvalidBban(String),
+    InvalidChecksum(String),
+}
+
+impl fmt::Display for ParseIbanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseIbanError::InvalidCharacters(iban) => write!(f, "{} has characters other than A-Z and 0-9", iban),
+            ParseIbanError::UnknownCountry(iban) => write!(f, "{} is not from a country with IBANs", iban),
+            ParseIbanError::InvalidLength(iban) => write!(f, "{} doesn't have the length of its country", iban),
+            ParseIbanError::InvalidBban(iban) => write!(f, "{} doesn't have the format of its country", iban),
+            ParseIbanError::InvalidChecksum(iban) => write!(f, "{} has wrong check digits", iban),
+        }
+    }
+}
+
+impl Serialize for ParseIbanError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Iban {
+    // Without whitespace, in upper case
+    electronic: String,
+}
+
+impl FromStr for Iban {
+    type Err = ParseIbanError;
+
+    fn from_str(iban: &str) -> Result<Iban, ParseIbanError> {
+        let electronic: String = iban.split_whitespace().collect::<String>().to_uppercase();
+        let iban_vo = get_iban_vo_from_str(&electronic);
+        if !iban_vo.is_alphanumeric {
+            return Err(ParseIbanError::InvalidCharacters(electronic));
+        }
+        if !iban_vo.is_valid_country {
+            return Err(ParseIbanError::UnknownCountry(electronic));
+        }
+        if !iban_vo.is_correct_length {
+            return Err(ParseIbanError::InvalidLength(electronic));
+        }
+        let types = country_data::get_bban_character_types(&iban_vo.format_country_code).unwrap_or_default();
+        if !electronic[4..].chars().zip(types).all(|(c, character_type)| character_type.accepts(c)) {
+            return Err(ParseIbanError::InvalidBban(electronic));
+        }
+        if !iban_vo.is_divisible_by_97 {
+            return Err(ParseIbanError::InvalidChecksum(electronic));
+        }
+        Ok(Iban { electronic })
+    }
+}
+
+// The IBAN in groups of four characters, the way it is printed
+impl fmt::Display for Iban {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<&str> = self
+            .electronic
+            .as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).unwrap_or(""))
+            .collect();
+        write!(f, "{}", groups.join(" "))
+    }
+}
+
+impl Iban {
+    pub fn electronic_str(&self) -> &str {
+        &self.electronic
+    }
+
+    pub fn country_code(&self) -> &str {
+        &self.electronic[0..2]
+    }
+
+    // The country whose IBAN format the IBAN has, which differs from the country code for the
+    // territories of `country_data::TERRITORIES`
+    pub fn format_country_code(&self) -> &str {
+        country_data::get_format_country(self.country_code())
+    }
+
+    pub fn check_digits_str(&self) -> &str {
+        &self.electronic[2..4]
+    }
+
+    pub fn bban(&self) -> &str {
+        &self.electronic[4..]
+    }
+
+    // `None` for the countries without a bank identifier in the IBAN registry
+    pub fn bank_identifier(&self) -> Option<&str> {
+        country_data::get_bank_identifier_range(self.format_country_code()).and_then(|range| self.bban().get(range))
+    }
+
+    // QR-IBANs of Switzerland and Liechtenstein have a QR institution identifier between 30000 and
+    // 31999 at the place of the bank identifier
+    pub fn qr_iid(&self) -> Option<u16> {
+        match self.country_code() {
+            "CH" | "LI" => self
+                .bank_identifier()
+                .and_then(|iid| iid.parse::<u16>().ok())
+                .filter(|iid| QR_IID_RANGE.contains(iid)),
+            _ => None,
+        }
+    }
+
+    pub fn is_qr_iban(&self) -> bool {
+        self.qr_iid().is_some()
+    }
+
+    // Payments to a QR-IBAN must carry a QR reference, payments to other IBANs must not
+    pub fn accepts_reference(&self, is_qr_reference: bool) -> bool {
+        self.is_qr_iban() == is_qr_reference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let iban: Iban = "de89 3704 0044 0532 0130 00".parse().unwrap();
+        assert_eq!(iban.electronic_str(), "DE89370400440532013000");
+        assert_eq!((iban.country_code(), iban.check_digits_str()), ("DE", "89"));
+        assert_eq!(iban.bban(), "370400440532013000");
+        assert_eq!(iban.bank_identifier(), Some("37040044"));
+        assert_eq!(iban.to_string(), "DE89 3704 0044 0532 0130 00");
+        assert_eq!("PL61109010140000071219812874".parse::<Iban>().unwrap().bank_identifier(), None);
+    }
+
+    #[test]
+    fn test_territory() {
+        let iban: Iban = "GP11 2004 1010 0505 0001 3M02 606".parse().unwrap();
+        assert_eq!((iban.country_code(), iban.format_country_code()), ("GP", "FR"));
+        assert_eq!(iban.bank_identifier(), Some("20041"));
+        let iban: Iban = "DE89370400440532013000".parse().unwrap();
+        assert_eq!(iban.format_country_code(), "DE");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |iban: &str| iban.parse::<Iban>().unwrap_err();
+        assert_eq!(error("DE89-3704"), ParseIbanError::InvalidCharacters(String::from("DE89-3704")));
+        assert_eq!(error("XX89370400440532013000"), ParseIbanError::UnknownCountry(String::from("XX89370400440532013000")));
+        assert_eq!(error("DE8937040044053201300"), ParseIbanError::InvalidLength(String::from("DE8937040044053201300")));
+        // The check digits are right, but German BBANs only have digits
+        assert_eq!(error("DE85A70400440532013000"), ParseIbanError::InvalidBban(String::from("DE85A70400440532013000")));
+        assert_eq!(error("DE88370400440532013000"), ParseIbanError::InvalidChecksum(String::from("DE88370400440532013000")));
+        assert!(matches!(error(""), ParseIbanError::InvalidCharacters(_) | ParseIbanError::UnknownCountry(_)));
+    }
+
+    #[test]
+    fn test_qr_iban() {
+        let qr_iban: Iban = "CH44 3199 9123 0008 8901 2".parse().unwrap();
+        assert_eq!(qr_iban.qr_iid(), Some(31999));
+        assert!(qr_iban.is_qr_iban());
+        assert!(qr_iban.accepts_reference(true));
+        assert!(!qr_iban.accepts_reference(false));
+
+        let iban: Iban = "CH9300762011623852957".parse().unwrap();
+        assert_eq!(iban.qr_iid(), None);
+        assert!(iban.accepts_reference(false));
+        assert!(!iban.accepts_reference(true));
+        assert!(!"DE89370400440532013000".parse::<Iban>().unwrap().is_qr_iban());
+    }
+}
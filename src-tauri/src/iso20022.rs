@@ -0,0 +1,399 @@
+// ISO 20022 payment messages: the credit transfer initiations (pain.001) that are uploaded to the
+// bank, and the statements (camt.053) and notifications (camt.054) that come back. Every IBAN and
+// BIC of the accounts and agents in the message is validated and reported with the path of its
+// element, e.g. /Document/CstmrCdtTrfInitn/PmtInf[2]/CdtTrfTxInf[1]/CdtrAcct/Id/IBAN.
+//
+// A transaction is valid if the IBANs and BICs inside it are valid, and the ones of the block it is
+// in, e.g. the debtor account of the payment information of a pain.001 or the account of a
+// statement.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use roxmltree::{Node, NodeId};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::epc_qr::is_valid_bic;
+use crate::get_iban_vo_from_str;
+
+// The BICs of financial institutions in the older and newer versions, and of other parties
+const BIC_ELEMENTS: [&str; 4] = ["BIC", "BICFI", "AnyBIC", "BICOrBEI"];
+// The references of a transaction, in the order they are looked for
+const REFERENCE_ELEMENTS: [&str; 3] = ["EndToEndId", "NtryRef", "AcctSvcrRef"];
+
+#[derive(PartialEq, Debug)]
+pub enum Iso20022Error {
+    InvalidXml(String),
+    UnsupportedMessage(String),
+}
+
+impl fmt::Display for Iso20022Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Iso20022Error::InvalidXml(msg) => write!(f, "the XML could not be read: {}", msg),
+            Iso20022Error::UnsupportedMessage(name) => {
+                write!(f, "{} is not a pain.001, camt.053 or camt.054 message", name)
+            }
+        }
+    }
+}
+
+impl Serialize for Iso20022Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct XmlIdentifier {
+    pub path: String,
+    // IBAN, or the BIC element, e.g. BICFI
+    pub element: String,
+    pub value: String,
+    pub is_valid: bool,
+}
+
+impl Serialize for XmlIdentifier {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("XmlIdentifier", 4)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("element", &self.element)?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("isValid", &self.is_valid)?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct TransactionResult {
+    pub path: String,
+    // The end to end id, or the reference of the entry in a statement
+    pub reference: Option<String>,
+    pub identifiers: Vec<XmlIdentifier>,
+}
+
+impl TransactionResult {
+    pub fn is_valid(&self) -> bool {
+        self.identifiers.iter().all(|identifier| identifier.is_valid)
+    }
+}
+
+impl Serialize for TransactionResult {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("TransactionResult", 4)?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.serialize_field("identifiers", &self.identifiers)?;
+        state.serialize_field("isValid", &self.is_valid())?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct MessageReport {
+    // E.g. pain.001.001.09, from the namespace of the message
+    pub message_type: String,
+    pub message_id: Option<String>,
+    // Every IBAN and BIC in the message, also the ones outside of transactions
+    pub identifiers: Vec<XmlIdentifier>,
+    pub transactions: Vec<TransactionResult>,
+}
+
+impl MessageReport {
+    pub fn is_valid(&self) -> bool {
+        self.identifiers.iter().all(|identifier| identifier.is_valid)
+    }
+}
+
+impl Serialize for MessageReport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("MessageReport", 5)?;
+        state.serialize_field("messageType", &self.message_type)?;
+        state.serialize_field("messageId", &self.message_id)?;
+        state.serialize_field("identifiers", &self.identifiers)?;
+        state.serialize_field("transactions", &self.transactions)?;
+        state.serialize_field("isValid", &self.is_valid())?;
+        state.end()
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.tag_name().name() == name)
+}
+
+fn text(node: Node) -> String {
+    node.text().unwrap_or("").trim().to_string()
+}
+
+// The paths of all elements, with the position among their siblings of the same name if there are
+// more. The children of every element are counted once, so large messages take linear time.
+fn paths(root: Node) -> HashMap<NodeId, String> {
+    let mut paths = HashMap::new();
+    paths.insert(root.id(), format!("/{}", root.tag_name().name()));
+    for parent in root.descendants().filter(|node| node.is_element()) {
+        let parent_path = paths[&parent.id()].clone();
+        let children: Vec<Node> = parent.children().filter(|child| child.is_element()).collect();
+        let mut same_name: HashMap<&str, usize> = HashMap::new();
+        for child in &children {
+            *same_name.entry(child.tag_name().name()).or_default() += 1;
+        }
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for child in children {
+            let name = child.tag_name().name();
+            let step = if same_name[name] > 1 {
+                let position = positions.entry(name).or_default();
+                *position += 1;
+                format!("{}[{}]", name, position)
+            } else {
+                name.to_string()
+            };
+            paths.insert(child.id(), format!("{}/{}", parent_path, step));
+        }
+    }
+    paths
+}
+
+// Statements and notifications list the transactions of an entry, entries without them are one
+// transaction
+fn is_transaction(node: &Node, is_initiation: bool, entries_with_details: &HashSet<NodeId>) -> bool {
+    match node.tag_name().name() {
+        "CdtTrfTxInf" => is_initiation,
+        "TxDtls" => !is_initiation,
+        "Ntry" => !is_initiation && !entries_with_details.contains(&node.id()),
+        _ => false,
+    }
+}
+
+// The child of the message that an element is in, e.g. the payment information or the statement
+fn block<'a, 'input>(node: Node<'a, 'input>, message: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    node.ancestors().find(|ancestor| ancestor.parent() == Some(message))
+}
+
+fn identifier(node: Node, paths: &HashMap<NodeId, String>) -> Option<XmlIdentifier> {
+    let element = node.tag_name().name();
+    let value = text(node);
+    let is_valid = match element {
+        // Only the IBAN of an account, other ids can also be called IBAN
+        "IBAN" if node.parent().map(|parent| parent.tag_name().name()) == Some("Id") => {
+            get_iban_vo_from_str(&value).is_valid()
+        }
+        _ if BIC_ELEMENTS.contains(&element) => is_valid_bic(&value),
+        _ => return None,
+    };
+    Some(XmlIdentifier {
+        path: paths[&node.id()].clone(),
+        element: element.to_string(),
+        value,
+        is_valid,
+    })
+}
+
+pub fn validate_message(xml: &str) -> Result<MessageReport, Iso20022Error> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| Iso20022Error::InvalidXml(e.to_string()))?;
+    let root = document.root_element();
+    // The message is the element inside Document, e.g. CstmrCdtTrfInitn
+    let message = root.children().find(|child| child.is_element()).unwrap_or(root);
+    let (is_initiation, default_type) = match message.tag_name().name() {
+        "CstmrCdtTrfInitn" => (true, "pain.001"),
+        "BkToCstmrStmt" => (false, "camt.053"),
+        "BkToCstmrDbtCdtNtfctn" => (false, "camt.054"),
+        name => return Err(Iso20022Error::UnsupportedMessage(name.to_string())),
+    };
+    let message_type = root
+        .tag_name()
+        .namespace()
+        .and_then(|namespace| namespace.rsplit(':').next())
+        .filter(|name| name.starts_with(default_type))
+        .unwrap_or(default_type)
+        .to_string();
+    let message_id = child(message, "GrpHdr").and_then(|header| child(header, "MsgId")).map(text);
+
+    let paths = paths(root);
+    let entries_with_details: HashSet<NodeId> = message
+        .descendants()
+        .filter(|node| node.tag_name().name() == "TxDtls")
+        .filter_map(|node| node.ancestors().find(|ancestor| ancestor.tag_name().name() == "Ntry"))
+        .map(|entry| entry.id())
+        .collect();
+    let is_transaction = |node: &Node| is_transaction(node, is_initiation, &entries_with_details);
+
+    // One pass puts every identifier into the transaction it is in, or the block if it is outside
+    // of transactions, by its position in the message
+    let nodes: Vec<(Node, XmlIdentifier)> = message
+        .descendants()
+        .filter_map(|node| identifier(node, &paths).map(|identifier| (node, identifier)))
+        .collect();
+    let mut by_transaction: HashMap<NodeId, Vec<usize>> = HashMap::new();
+    let mut by_block: HashMap<NodeId, Vec<usize>> = HashMap::new();
+    for (i, &(node, _)) in nodes.iter().enumerate() {
+        match node.ancestors().find(is_transaction) {
+            Some(transaction) => by_transaction.entry(transaction.id()).or_default().push(i),
+            None => {
+                if let Some(block) = block(node, message) {
+                    by_block.entry(block.id()).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    let transactions = message
+        .descendants()
+        .filter(is_transaction)
+        .map(|transaction| {
+            let inside = by_transaction.get(&transaction.id()).map(Vec::as_slice).unwrap_or(&[]);
+            let of_block = block(transaction, message)
+                .and_then(|block| by_block.get(&block.id()))
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            // Both lists are in the order of the message, merged they stay in it
+            let mut positions = [inside, of_block].concat();
+            positions.sort_unstable();
+            TransactionResult {
+                path: paths[&transaction.id()].clone(),
+                reference: REFERENCE_ELEMENTS.iter().find_map(|name| {
+                    transaction.descendants().find(|node| node.tag_name().name() == *name).map(text)
+                }),
+                identifiers: positions.into_iter().map(|i| nodes[i].1.clone()).collect(),
+            }
+        })
+        .collect();
+    Ok(MessageReport {
+        message_type,
+        message_id,
+        identifiers: nodes.into_iter().map(|(_, identifier)| identifier).collect(),
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const PAIN_001: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.03">
+  <CstmrCdtTrfInitn>
+    <GrpHdr><MsgId>BATCH-1</MsgId><NbOfTxs>2</NbOfTxs><InitgPty><Nm>Acme</Nm></InitgPty></GrpHdr>
+    <PmtInf>
+      <PmtInfId>P1</PmtInfId>
+      <DbtrAcct><Id><IBAN>DE89370400440532013000</IBAN></Id></DbtrAcct>
+      <DbtrAgt><FinInstnId><BIC>COBADEFFXXX</BIC></FinInstnId></DbtrAgt>
+      <CdtTrfTxInf>
+        <PmtId><EndToEndId>E2E-1</EndToEndId></PmtId>
+        <CdtrAgt><FinInstnId><BIC>BNPAFRPP</BIC></FinInstnId></CdtrAgt>
+        <CdtrAcct><Id><IBAN>FR1420041010050500013M02606</IBAN></Id></CdtrAcct>
+      </CdtTrfTxInf>
+      <CdtTrfTxInf>
+        <PmtId><EndToEndId>E2E-2</EndToEndId></PmtId>
+        <CdtrAcct><Id><IBAN>GB82WEST12345698765431</IBAN></Id></CdtrAcct>
+      </CdtTrfTxInf>
+    </PmtInf>
+    <PmtInf>
+      <PmtInfId>P2</PmtInfId>
+      <DbtrAcct><Id><IBAN>NL91ABNA0417164300</IBAN></Id></DbtrAcct>
+      <CdtTrfTxInf>
+        <PmtId><EndToEndId>E2E-3</EndToEndId></PmtId>
+        <CdtrAcct><Id><IBAN>BE68539007547034</IBAN></Id></CdtrAcct>
+      </CdtTrfTxInf>
+    </PmtInf>
+  </CstmrCdtTrfInitn>
+</Document>"#;
+
+    const CAMT_053: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>STMT-1</MsgId></GrpHdr>
+    <Stmt>
+      <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Svcr><FinInstnId><BICFI>COBADEFF</BICFI></FinInstnId></Svcr></Acct>
+      <Ntry>
+        <NtryRef>N1</NtryRef>
+        <NtryDtls>
+          <TxDtls>
+            <Refs><EndToEndId>E2E-1</EndToEndId></Refs>
+            <RltdPties><DbtrAcct><Id><IBAN>FR1420041010050500013M02606</IBAN></Id></DbtrAcct></RltdPties>
+            <RltdAgts><DbtrAgt><FinInstnId><BICFI>BNPA-FRPP</BICFI></FinInstnId></DbtrAgt></RltdAgts>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry><NtryRef>N2</NtryRef><AcctSvcrRef>BANK-2</AcctSvcrRef></Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn test_pain_001() {
+        let report = validate_message(PAIN_001).unwrap();
+        assert_eq!(report.message_type, "pain.001.001.03");
+        assert_eq!(report.message_id.as_deref(), Some("BATCH-1"));
+        assert_eq!(report.identifiers.len(), 7);
+        assert!(!report.is_valid());
+
+        assert_eq!(report.transactions.len(), 3);
+        let (first, second, third) = (&report.transactions[0], &report.transactions[1], &report.transactions[2]);
+        assert_eq!(first.path, "/Document/CstmrCdtTrfInitn/PmtInf[1]/CdtTrfTxInf[1]");
+        assert_eq!(first.reference.as_deref(), Some("E2E-1"));
+        // The debtor account and agent of the payment information, and the creditor agent and account
+        assert_eq!(first.identifiers.len(), 4);
+        assert!(first.is_valid());
+        assert!(!second.is_valid());
+        let invalid: Vec<_> = second.identifiers.iter().filter(|identifier| !identifier.is_valid).collect();
+        assert_eq!(invalid[0].path, "/Document/CstmrCdtTrfInitn/PmtInf[1]/CdtTrfTxInf[2]/CdtrAcct/Id/IBAN");
+        let values: Vec<_> = third.identifiers.iter().map(|identifier| identifier.value.as_str()).collect();
+        assert_eq!(values, ["NL91ABNA0417164300", "BE68539007547034"]);
+        assert!(third.is_valid());
+    }
+
+    #[test]
+    fn test_large_message() {
+        let transaction = "<CdtTrfTxInf><PmtId><EndToEndId>E2E</EndToEndId></PmtId>\
+            <CdtrAcct><Id><IBAN>BE68539007547034</IBAN></Id></CdtrAcct></CdtTrfTxInf>";
+        let xml = format!(
+            "<Document><CstmrCdtTrfInitn><PmtInf><DbtrAcct><Id><IBAN>DE89370400440532013000</IBAN></Id></DbtrAcct>\
+            {}</PmtInf></CstmrCdtTrfInitn></Document>",
+            transaction.repeat(5000)
+        );
+        let report = validate_message(&xml).unwrap();
+        assert_eq!(report.transactions.len(), 5000);
+        assert_eq!(report.transactions[4999].path, "/Document/CstmrCdtTrfInitn/PmtInf/CdtTrfTxInf[5000]");
+        assert!(report.transactions.iter().all(|transaction| transaction.identifiers.len() == 2));
+    }
+
+    #[test]
+    fn test_camt_053() {
+        let report = validate_message(CAMT_053).unwrap();
+        assert_eq!(report.message_type, "camt.053.001.08");
+        assert_eq!(report.transactions.len(), 2);
+        let transaction = &report.transactions[0];
+        assert_eq!(transaction.path, "/Document/BkToCstmrStmt/Stmt/Ntry[1]/NtryDtls/TxDtls");
+        assert_eq!(transaction.reference.as_deref(), Some("E2E-1"));
+        assert_eq!(transaction.identifiers.len(), 4);
+        assert_eq!(transaction.identifiers.iter().filter(|identifier| !identifier.is_valid).count(), 1);
+        // An entry without transaction details is one transaction, with the account of the statement
+        let entry = &report.transactions[1];
+        assert_eq!(entry.reference.as_deref(), Some("N2"));
+        assert!(entry.is_valid());
+        assert_eq!(entry.identifiers.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        assert!(matches!(validate_message("<Document>"), Err(Iso20022Error::InvalidXml(_))));
+        assert_eq!(
+            validate_message("<Document><CstmrDrctDbtInitn/></Document>"),
+            Err(Iso20022Error::UnsupportedMessage(String::from("CstmrDrctDbtInitn")))
+        );
+        let report = validate_message("<Document><BkToCstmrDbtCdtNtfctn><GrpHdr/></BkToCstmrDbtCdtNtfctn></Document>").unwrap();
+        assert_eq!(report.message_type, "camt.054");
+        assert!(report.transactions.is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+// ISO 7064 check character systems. The input is alphanumeric, letters have the values A = 10, ..., Z = 35.
+// MOD 97-10 expands letters to two digits first, which is how IBANs, RF references, Creditor
+// Identifiers and LEIs use it. The check characters are appended at the end of the input.
+use std::fmt;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum System {
+    // Pure system over digits, one check character 0-9 or X
+    #[serde(rename = "MOD 11-2")]
+    Mod11_2,
+    // Pure system over alphanumerics, one check character 0-9, A-Z or *
+    #[serde(rename = "MOD 37-2")]
+    Mod37_2,
+    // Pure system, two check digits
+    #[serde(rename = "MOD 97-10")]
+    Mod97_10,
+    // Hybrid system over digits, one check digit
+    #[serde(rename = "MOD 11,10")]
+    Mod11_10,
+    // Hybrid system over alphanumerics, one check character 0-9 or A-Z
+    #[serde(rename = "MOD 37,36")]
+    Mod37_36,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Iso7064Error {
+    Empty,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for Iso7064Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Iso7064Error::Empty => write!(f, "the input is empty"),
+            Iso7064Error::InvalidCharacter(c) => write!(f, "{:?} cannot be used with this system", c),
+        }
+    }
+}
+
+impl Serialize for Iso7064Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+const CHECK_CHARACTERS: &[u8; 37] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ*";
+
+impl System {
+    fn numeric_only(self) -> bool {
+        matches!(self, System::Mod11_2 | System::Mod11_10)
+    }
+
+    fn check_length(self) -> usize {
+        match self {
+            System::Mod97_10 => 2,
+            _ => 1,
+        }
+    }
+
+    fn value(self, c: char) -> Result<u32, Iso7064Error> {
+        let value = c.to_digit(36).filter(|_| c.is_ascii());
+        match value {
+            Some(v) if v < 10 || !self.numeric_only() => Ok(v),
+            _ => Err(Iso7064Error::InvalidCharacter(c)),
+        }
+    }
+
+    fn values(self, input: &str) -> Result<Vec<u32>, Iso7064Error> {
+        if input.is_empty() {
+            return Err(Iso7064Error::Empty);
+        }
+        input.chars().map(|c| self.value(c)).collect()
+    }
+
+    // The state after processing the input, before the check characters are added
+    fn state(self, values: &[u32]) -> u32 {
+        match self {
+            System::Mod11_2 => pure(values, 11, 2),
+            System::Mod37_2 => pure(values, 37, 2),
+            System::Mod97_10 => mod97_10_remainder_of(values),
+            System::Mod11_10 => hybrid(values, 10),
+            System::Mod37_36 => hybrid(values, 36),
+        }
+    }
+
+    // Computes the check characters for the input
+    pub fn compute(self, input: &str) -> Result<String, Iso7064Error> {
+        let state = self.state(&self.values(input)?);
+        Ok(match self {
+            // Appending "00" and taking 98 minus the remainder makes the whole divisible to 1
+            System::Mod97_10 => format!("{:02}", 98 - (state * 100) % 97),
+            // MOD 11-2 writes the value 10 as X
+            System::Mod11_2 => match (12 - state) % 11 {
+                10 => String::from("X"),
+                check => char_for(check),
+            },
+            System::Mod37_2 => char_for((38 - state) % 37),
+            System::Mod11_10 => char_for((11 - state) % 10),
+            System::Mod37_36 => char_for((37 - state) % 36),
+        })
+    }
+
+    // Checks input that ends with its check characters
+    pub fn verify(self, input: &str) -> bool {
+        if input.len() <= self.check_length() || !input.is_ascii() {
+            return false;
+        }
+        let (data, check) = input.split_at(input.len() - self.check_length());
+        match self {
+            System::Mod97_10 => mod97_10_remainder(input) == Ok(1),
+            _ => self.compute(data).is_ok_and(|expected| expected == check),
+        }
+    }
+}
+
+fn char_for(value: u32) -> String {
+    (CHECK_CHARACTERS[value as usize] as char).to_string()
+}
+
+fn pure(values: &[u32], modulus: u32, radix: u32) -> u32 {
+    values.iter().fold(0, |p, &v| ((p + v) * radix) % modulus)
+}
+
+fn hybrid(values: &[u32], modulus: u32) -> u32 {
+    values.iter().fold(modulus, |p, &v| {
+        let s = match (p + v) % modulus {
+            0 => modulus,
+            s => s,
+        };
+        (s * 2) % (modulus + 1)
+    })
+}
+
+fn mod97_10_remainder_of(values: &[u32]) -> u32 {
+    // Letters count as two digits
+    values.iter().fold(0, |r, &v| {
+        if v < 10 {
+            (r * 10 + v) % 97
+        } else {
+            (r * 100 + v) % 97
+        }
+    })
+}
+
+// The remainder on division by 97 of the number the input represents once letters are replaced by
+// two digits. Computed piece by piece, as the numbers of the longer IBANs don't fit in a u128.
+pub fn mod97_10_remainder(input: &str) -> Result<u32, Iso7064Error> {
+    System::Mod97_10
+        .values(input)
+        .map(|values| mod97_10_remainder_of(&values))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_known_vectors() {
+        // Examples from ISO 7064 and the identifiers that use it
+        assert_eq!(System::Mod11_2.compute("0794"), Ok(String::from("0")));
+        assert_eq!(System::Mod11_2.compute("000000021825009"), Ok(String::from("7")));
+        assert_eq!(System::Mod11_2.compute("000000021694233"), Ok(String::from("X")));
+        assert_eq!(System::Mod37_2.compute("G123498654321"), Ok(String::from("H")));
+        assert_eq!(System::Mod97_10.compute("794"), Ok(String::from("44")));
+        assert_eq!(System::Mod11_10.compute("0794"), Ok(String::from("5")));
+        assert_eq!(System::Mod11_10.compute("13669597"), Ok(String::from("6")));
+        assert_eq!(System::Mod37_36.compute("A12425GABC1234002"), Ok(String::from("M")));
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(System::Mod11_2.verify("000000021694233X"));
+        assert!(System::Mod37_2.verify("G123498654321H"));
+        assert!(System::Mod97_10.verify("79444"));
+        assert!(System::Mod11_10.verify("07945"));
+        assert!(System::Mod37_36.verify("A12425GABC1234002M"));
+        assert!(!System::Mod37_36.verify("A12425GABC1234003M"));
+        assert!(!System::Mod11_2.verify("X"));
+        assert!(!System::Mod97_10.verify("ÖÖÖ"));
+    }
+
+    #[test]
+    fn test_mod97_10_remainder() {
+        // An IBAN, rearranged: the country code and check digits moved to the end
+        assert_eq!(mod97_10_remainder("370400440532013000DE89"), Ok(1));
+        assert_eq!(mod97_10_remainder("131489370400440532013000"), mod97_10_remainder("DE89370400440532013000"));
+        assert_eq!(
+            mod97_10_remainder("12345678901234567890123456789012345678901234567890"),
+            Ok(16)
+        );
+        assert_eq!(mod97_10_remainder(""), Err(Iso7064Error::Empty));
+        assert_eq!(mod97_10_remainder("12-3"), Err(Iso7064Error::InvalidCharacter('-')));
+    }
+
+    #[test]
+    fn test_invalid_characters() {
+        assert_eq!(System::Mod11_2.compute("12A"), Err(Iso7064Error::InvalidCharacter('A')));
+        assert_eq!(System::Mod11_10.compute("12 3"), Err(Iso7064Error::InvalidCharacter(' ')));
+        assert_eq!(System::Mod37_36.compute(""), Err(Iso7064Error::Empty));
+    }
+
+    const SYSTEMS: [System; 5] = [
+        System::Mod11_2,
+        System::Mod37_2,
+        System::Mod97_10,
+        System::Mod11_10,
+        System::Mod37_36,
+    ];
+
+    proptest! {
+        #[test]
+        fn computed_check_characters_verify(input in "[0-9A-Z]{1,40}") {
+            for system in SYSTEMS {
+                if let Ok(check) = system.compute(&input) {
+                    prop_assert!(system.verify(&(input.clone() + &check)), "{:?}", system);
+                }
+            }
+        }
+
+        #[test]
+        fn single_substitutions_are_detected(input in "[0-9]{2,30}", position in 0usize..30, digit in 0u32..10) {
+            let position = position % input.len();
+            let original = input.chars().nth(position).unwrap().to_digit(10).unwrap();
+            prop_assume!(original != digit);
+            let mut changed: Vec<char> = input.chars().collect();
+            changed[position] = std::char::from_digit(digit, 10).unwrap();
+            let changed: String = changed.into_iter().collect();
+            for system in SYSTEMS {
+                let check = system.compute(&input).unwrap();
+                prop_assert!(!system.verify(&(changed.clone() + &check)), "{:?}", system);
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// ISO 17442 Legal Entity Identifiers: a 4 character LOU prefix, 2 reserved characters,
+// a 12 character entity part and 2 MOD 97-10 check digits over the whole identifier.
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::iso7064;
+
+const LEI_LENGTH: usize = 20;
+
+#[derive(PartialEq, Debug)]
+pub struct LeiVO {
+    pub lei: String,
+    pub is_alphanumeric: bool,
+    pub is_correct_length: bool,
+    pub is_valid_checksum: bool,
+}
+
+impl LeiVO {
+    pub fn is_valid(&self) -> bool {
+        self.is_alphanumeric && self.is_correct_length && self.is_valid_checksum
+    }
+
+    // The prefix of the Local Operating Unit that issued the LEI
+    pub fn lou_prefix(&self) -> Option<&str> {
+        self.lei.get(0..4).filter(|_| self.is_correct_length)
+    }
+
+    pub fn entity_part(&self) -> Option<&str> {
+        self.lei.get(6..18).filter(|_| self.is_correct_length)
+    }
+}
+
+impl Serialize for LeiVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("LeiVO", 6)?;
+        state.serialize_field("lei", &self.lei)?;
+        state.serialize_field("isAlphanumeric", &self.is_alphanumeric)?;
+        state.serialize_field("isCorrectLength", &self.is_correct_length)?;
+        state.serialize_field("isValidChecksum", &self.is_valid_checksum)?;
+        state.serialize_field("louPrefix", &self.lou_prefix())?;
+        state.serialize_field("entityPart", &self.entity_part())?;
+        state.end()
+    }
+}
+
+pub fn get_lei_vo_from_str(lei: &str) -> LeiVO {
+    let lei: String = lei.split_whitespace().collect::<String>().to_uppercase();
+    let is_alphanumeric = lei.chars().all(|c| c.is_ascii_alphanumeric());
+    let is_correct_length = lei.len() == LEI_LENGTH;
+    LeiVO {
+        is_valid_checksum: is_alphanumeric && is_correct_length && iso7064::System::Mod97_10.verify(&lei),
+        lei,
+        is_alphanumeric,
+        is_correct_length,
+    }
+}
+
+// Appends the check digits to the first 18 characters of an LEI
+pub fn generate_lei(base: &str) -> Option<String> {
+    let base = base.to_uppercase();
+    if base.len() != LEI_LENGTH - 2 || !base.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let check_digits = iso7064::System::Mod97_10.compute(&base).ok()?;
+    Some(base + &check_digits)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_valid_lei() {
+        let lei = get_lei_vo_from_str("5493001KJTIIGC8Y1R12");
+        assert!(lei.is_valid());
+        assert_eq!(lei.lou_prefix(), Some("5493"));
+        assert_eq!(lei.entity_part(), Some("1KJTIIGC8Y1R"));
+
+        assert!(get_lei_vo_from_str("hwupkr0mpou8fgxbt394").is_valid());
+        assert!(get_lei_vo_from_str("5299 00T8 BM49 AURS DO55").is_valid());
+    }
+
+    #[test]
+    fn test_invalid_lei() {
+        assert_eq!(
+            get_lei_vo_from_str("5493001KJTIIGC8Y1R13"),
+            LeiVO {
+                lei: String::from("5493001KJTIIGC8Y1R13"),
+                is_alphanumeric: true,
+                is_correct_length: true,
+                is_valid_checksum: false,
+            }
+        );
+        let too_short = get_lei_vo_from_str("5493001KJTIIGC8Y1R1");
+        assert!(!too_short.is_correct_length);
+        assert_eq!(too_short.lou_prefix(), None);
+        assert!(!get_lei_vo_from_str("5493001KJTIIGC8Y1R1-").is_alphanumeric);
+    }
+
+    #[test]
+    fn test_generate_lei() {
+        assert_eq!(generate_lei("5493001KJTIIGC8Y1R"), Some(String::from("5493001KJTIIGC8Y1R12")));
+        assert_eq!(generate_lei("5493001KJTIIGC8Y1"), None);
+    }
+}
@@ -0,0 +1,451 @@
+// The validation of IBANs, and of the other identifiers and files of payments in the modules. The
+// app in main.rs and the command line interface are built on it, and so are the fuzz targets in
+// fuzz/ and, with the proptest or arbitrary feature, the IBAN strategies of other crates.
+use std::fmt;
+use std::sync::Arc;
+
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+
+pub mod bundesbank;
+pub mod cli;
+pub mod country_data;
+pub mod credit_transfer;
+pub mod creditor_id;
+pub mod csv_input;
+pub mod dir_scan;
+pub mod documents;
+pub mod domestic_account;
+pub mod domestic_notation;
+pub mod epc_qr;
+pub mod explain;
+pub mod generate;
+pub mod iban;
+pub mod iso20022;
+pub mod iso7064;
+pub mod lei;
+pub mod national_check;
+pub mod partial;
+pub mod pseudonymize;
+pub mod qr_decode;
+pub mod redact;
+pub mod references;
+pub mod rf_reference;
+#[cfg(any(test, feature = "proptest", feature = "arbitrary"))]
+pub mod strategies;
+pub mod swift_mt;
+pub mod swiss_qr;
+pub mod text_scan;
+pub mod vocalink;
+
+#[derive(PartialEq, Debug)]
+pub struct IbanVO {
+    pub iban: String,
+    pub country_code: String,
+    // Territories use the format of another country, e.g. GP IBANs have the format of FR
+    pub format_country_code: String,
+    pub is_alphanumeric: bool,
+    pub is_valid_country: bool,
+    pub is_correct_length: bool,
+    pub is_divisible_by_97: bool,
+    // The check of the account number against its bank, for German IBANs with the bank code file
+    // of the Bundesbank and for UK IBANs with the Vocalink modulus file in the `BankFiles`
+    pub national_account_check: Option<national_check::AccountCheck>,
+}
+
+impl IbanVO {
+    pub fn is_valid(&self) -> bool {
+        self.is_alphanumeric && self.is_valid_country && self.is_correct_length && self.is_divisible_by_97
+    }
+}
+
+impl Serialize for IbanVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("IbanVO", 8)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.serialize_field("countryCode", &self.country_code)?;
+        state.serialize_field("formatCountryCode", &self.format_country_code)?;
+        state.serialize_field("isValidCountry", &self.is_valid_country)?;
+        state.serialize_field("isAlphanumeric", &self.is_alphanumeric)?;
+        state.serialize_field("isCorrectLength", &self.is_correct_length)?;
+        state.serialize_field("isDivisibleBy97", &self.is_divisible_by_97)?;
+        state.serialize_field("nationalAccountCheck", &self.national_account_check)?;
+        state.end()
+    }
+}
+
+// The files of the national account checks. They are read by the app and the command line
+// interface, which pass the loaded ones in, the validation itself doesn't look them up
+#[derive(Clone, Default)]
+pub struct BankFiles {
+    pub bank_directory: Option<Arc<bundesbank::BankDirectory>>,
+    pub modulus_tables: Option<Arc<vocalink::ModulusTables>>,
+}
+
+impl BankFiles {
+    // The files loaded into the app or by the command line interface
+    pub fn loaded() -> BankFiles {
+        BankFiles {
+            bank_directory: bundesbank::bank_directory().ok(),
+            modulus_tables: vocalink::modulus_tables().ok(),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum IbanError {
+    NoIbans,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for IbanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IbanError::NoIbans => write!(f, "no IBANs were given"),
+            IbanError::InvalidCharacter(c) => write!(f, "{:?} is not a letter A-Z or a digit", c),
+        }
+    }
+}
+
+impl Serialize for IbanError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_error(self, serializer)
+    }
+}
+
+// Errors are sent to the frontend as an object with the kind of error, which is the name of the
+// enum variant, and a message that can be shown to the user
+fn serialize_error<E, S>(error: &E, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: fmt::Debug + fmt::Display,
+    S: Serializer,
+{
+    let kind: String = format!("{:?}", error)
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .collect();
+    let mut state = serializer.serialize_struct("Error", 2)?;
+    state.serialize_field("kind", &kind)?;
+    state.serialize_field("message", &error.to_string())?;
+    state.end()
+}
+
+fn is_alphanumeric(iban: &str) -> bool {
+    iban.chars().all(|c| {
+        c.is_alphanumeric()
+    })
+}
+
+
+fn replace_letters_with_numbers(iban: &str) -> Result<String, IbanError> {
+    iban.to_uppercase().chars().map(|c| {
+        // to_digit also accepts letters of other alphabets, but they have no value in an IBAN
+        c.to_digit(36)
+            .filter(|_| c.is_ascii())
+            .map(|digit| digit.to_string())
+            .ok_or(IbanError::InvalidCharacter(c))
+    }).collect()
+}
+
+fn divide_by_97(iban: &str) -> bool {
+    iso7064::System::Mod97_10.verify(iban)
+}
+
+// The validation without the national account check
+pub fn get_iban_vo_from_str(iban: &str) -> IbanVO {
+    get_iban_vo_with(iban, &BankFiles::default())
+}
+
+pub fn get_iban_vo_with(iban: &str, bank_files: &BankFiles) -> IbanVO {
+    // Get country code from iban string, letters of other alphabets can't be sliced by bytes
+    let country_code: &str = iban.get(0..2).unwrap_or("");
+    let format_country_code = country_data::get_format_country(country_code);
+    let mut iban_vo = IbanVO {
+        iban: String::from(iban),
+        country_code: String::from(country_code),
+        format_country_code: String::from(format_country_code),
+        is_alphanumeric: false,
+        is_valid_country: false,
+        is_correct_length: false,
+        is_divisible_by_97: false,
+        national_account_check: None,
+    };
+
+    if iban.len() < 2 {
+        // Sanity check so program won't crash in case the string length is less 2
+        return iban_vo;
+    }
+     let is_iban_alphanumeric = is_alphanumeric(iban);
+    if !is_iban_alphanumeric {
+        return iban_vo;
+    } else {
+        iban_vo.is_alphanumeric = true;
+    }
+    // Lookup valid country codes, and look up valid length per country
+    let country_codes_and_length = country_data::get_country_codes_and_length();
+    
+    // Has valid country code
+    iban_vo.is_valid_country = country_codes_and_length.contains_key(format_country_code);
+
+    let country_length = country_codes_and_length.get(format_country_code);
+    iban_vo.is_correct_length = country_length == Some(&iban.len());
+    if iban_vo.is_correct_length {
+        iban_vo.national_account_check = match format_country_code {
+            "DE" => bank_files.bank_directory.as_ref().and_then(|directory| directory.check_bban(iban.get(4..)?)),
+            "GB" => bank_files.modulus_tables.as_ref().and_then(|tables| tables.check_bban(iban.get(4..)?)),
+            _ => None,
+        };
+    }
+
+    // move the first four characters to the end of the string
+    let iban: String = iban.chars().skip(4).chain(iban.chars().take(4)).collect();
+
+    // replace each letter with number, and check if the IBAN is divisible by 97
+    iban_vo.is_divisible_by_97 = replace_letters_with_numbers(&iban).is_ok_and(|iban| divide_by_97(&iban));
+    iban_vo
+
+}
+
+// Tests: Placing unit tests in the same file is the idiomatic way of doing it in rust
+#[cfg(test)] // Do not include this in the executable
+mod tests {   
+
+    use super::*; // Brings in all methods in this file in the tests scope
+
+    #[test]
+    fn test_letter_replacer() {
+        let iban = "DE89370400440532013000";
+        let expected = "131489370400440532013000";
+        assert_eq!(replace_letters_with_numbers(iban), Ok(String::from(expected)));
+
+        let iban_two = "89370400440532013DE000";
+        let expected_two = "893704004405320131314000";
+        assert_eq!(replace_letters_with_numbers(iban_two), Ok(String::from(expected_two)));
+
+        assert_eq!(replace_letters_with_numbers("DEÖ9"), Err(IbanError::InvalidCharacter('Ö')));
+    }
+
+    #[test]
+    fn test_valid_iban() {
+        let iban1 = "BH02CITI00001077181611";
+        let iban2 = "DJ2110002010010409943020008";
+        let iban3 = "SV43ACAT00000000000000123123";
+        let iban4 = "GT20AGRO00000000001234567890";
+        let with_non_alphanumeric_char = "!GT20AGRO00000000001234567890";
+
+        assert_eq!(get_iban_vo_from_str(iban1), IbanVO {
+            iban: String::from(iban1),
+            country_code: String::from("BH"),
+            format_country_code: String::from("BH"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: true,
+            is_divisible_by_97: true,
+            national_account_check: None,
+        });
+        assert_eq!(get_iban_vo_from_str(iban2), IbanVO {
+            iban: String::from(iban2),
+            country_code: String::from("DJ"),
+            format_country_code: String::from("DJ"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: true,
+            is_divisible_by_97: true,
+            national_account_check: None,
+        });
+        assert_eq!(get_iban_vo_from_str(iban3), IbanVO {
+            iban: String::from(iban3),
+            country_code: String::from("SV"),
+            format_country_code: String::from("SV"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: true,
+            is_divisible_by_97: true,
+            national_account_check: None,
+        });
+        assert_eq!(get_iban_vo_from_str(iban4), IbanVO {
+            iban: String::from(iban4),
+            country_code: String::from("GT"),
+            format_country_code: String::from("GT"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: true,
+            is_divisible_by_97: true,
+            national_account_check: None,
+        });
+        assert_eq!(get_iban_vo_from_str(with_non_alphanumeric_char), IbanVO {
+            iban: String::from(with_non_alphanumeric_char),
+            country_code: String::from("!G"),
+            format_country_code: String::from("!G"),
+            is_alphanumeric: false,
+            is_valid_country: false,
+            is_correct_length: false,
+            is_divisible_by_97: false,
+            national_account_check: None,
+        });
+    }
+
+    #[test]
+    fn test_long_iban() {
+        let iban = "MT84MALT011000012345MTLCAST001S";
+        assert!(get_iban_vo_from_str(iban).is_valid());
+    }
+
+    #[test]
+    fn test_not_divisible_by_97() {
+        let iban = "DE89370400440532013001";
+        let expected_output = IbanVO {
+            iban: String::from(iban),
+            country_code: String::from("DE"),
+            format_country_code: String::from("DE"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: true,
+            is_divisible_by_97: false,
+            national_account_check: None,
+        };
+        assert_eq!(get_iban_vo_from_str(iban), expected_output);
+    }
+
+    #[test]
+    fn test_is_alphanumeric() {
+        let num1 = "LKSDJFLKJHLKJ39872389476";
+        assert!(is_alphanumeric(num1));
+        let num2 = "ASDÖLKölsdgjlknqåeptoilkbxöcbjewrjiqopwer923874013658971";
+        assert!(is_alphanumeric(num2));
+        let num3 = "ABC_";
+        assert!(!is_alphanumeric(num3));
+        let num4 = "ABC;";
+        assert!(!is_alphanumeric(num4));
+    }
+
+    #[test]
+    fn test_incorrect_length() {
+        let iban = "AT4832000000123234245864";
+        let expected_output = IbanVO {
+            iban: String::from(iban),
+            country_code: String::from("AT"),
+            format_country_code: String::from("AT"),
+            is_alphanumeric: true,
+            is_valid_country: true,
+            is_correct_length: false,
+            is_divisible_by_97: false,
+            national_account_check: None,
+        };
+        assert_eq!(get_iban_vo_from_str(iban), expected_output);
+    }
+
+    #[test]
+    fn test_invalid_country_code() {
+        let iban = "XX89370400440532013000";
+        let expected_output = IbanVO {
+            iban: String::from(iban),
+            country_code: String::from("XX"),
+            format_country_code: String::from("XX"),
+            is_alphanumeric: true,
+            is_valid_country: false,
+            is_correct_length: false,
+            is_divisible_by_97: false,
+            national_account_check: None,
+        };
+        assert_eq!(get_iban_vo_from_str(iban), expected_output);
+    }
+
+    #[test]
+    fn test_territories() {
+        for iban in ["GP1120041010050500013M02606", "JE90NWBK60161331926819", "AX2112345600000785"] {
+            assert!(get_iban_vo_from_str(iban).is_valid(), "{}", iban);
+        }
+        let iban_vo = get_iban_vo_from_str("GP1120041010050500013M02606");
+        assert_eq!((iban_vo.country_code.as_str(), iban_vo.format_country_code.as_str()), ("GP", "FR"));
+        // The format of FR has 27 characters
+        assert!(!get_iban_vo_from_str("GP1120041010050500013M0260").is_correct_length);
+    }
+
+    #[test]
+    fn test_letters_of_other_alphabets() {
+        // Accepted as alphanumeric, but they can't be part of a valid IBAN
+        let iban_vo = get_iban_vo_from_str("DEÖ9370400440532013000");
+        assert!(iban_vo.is_alphanumeric);
+        assert!(!iban_vo.is_divisible_by_97);
+        assert!(!get_iban_vo_from_str("dÖ89370400440532013000").is_valid_country);
+    }
+
+    #[test]
+    fn test_german_account_check() {
+        let bank_files = BankFiles { bank_directory: Some(Arc::new(bundesbank::tests::directory())), modulus_tables: None };
+        let check = get_iban_vo_with("DE89370400440532013000", &bank_files).national_account_check.unwrap();
+        assert_eq!((check.method.as_deref(), check.is_valid), (Some("13"), Some(true)));
+        // The IBAN check digits are right, the check digit of the account number isn't
+        let iban_vo = get_iban_vo_with("DE84370400440532113000", &bank_files);
+        assert!(iban_vo.is_valid());
+        assert_eq!(iban_vo.national_account_check.unwrap().is_valid, Some(false));
+        assert_eq!(get_iban_vo_with("DE89370400440532013000", &BankFiles::default()).national_account_check, None);
+        assert_eq!(get_iban_vo_from_str("DE89370400440532013000").national_account_check, None);
+    }
+
+    #[test]
+    fn test_uk_account_check() {
+        let bank_files = BankFiles {
+            bank_directory: None,
+            modulus_tables: Some(Arc::new(vocalink::tests::specification_tables())),
+        };
+        let check = get_iban_vo_with("GB70NWBK08999966374958", &bank_files).national_account_check.unwrap();
+        assert_eq!((check.method.as_deref(), check.is_valid), (Some("MOD10"), Some(true)));
+        let iban_vo = get_iban_vo_with("GB43NWBK08999966374959", &bank_files);
+        assert!(iban_vo.is_valid());
+        assert_eq!(iban_vo.national_account_check.unwrap().is_valid, Some(false));
+        assert_eq!(get_iban_vo_from_str("GB70NWBK08999966374958").national_account_check, None);
+    }
+
+    // Every function that takes user input is run with arbitrary strings, none of them may panic
+    proptest::proptest! {
+        #[test]
+        fn no_input_panics(input in "\\PC{0,40}") {
+            get_iban_vo_from_str(&input);
+            partial::get_partial_iban_vo_from_str(&input);
+            let _ = explain::explain_checksum(&input);
+            creditor_id::get_creditor_id_vo_from_str(&input);
+            lei::get_lei_vo_from_str(&input);
+            for country_code in domestic_account::COUNTRIES {
+                domestic_account::get_domestic_account_vo_from_str(country_code, &input);
+            }
+            rf_reference::get_rf_reference_vo_from_str(&input);
+            references::get_reference_vo(&input, Some(&input));
+            let _ = references::detect_scheme(&input, Some(&input));
+            let _ = epc_qr::parse_payload(&input);
+            let _ = qr_decode::decode_payload(&input);
+            let _ = swiss_qr::parse_and_validate(&input);
+            let _ = csv_input::validate_csv(&input, None, None, None);
+            text_scan::find_ibans(&input);
+            let _ = redact::redact(&input, redact::RedactionMode::Mask);
+            for file in ["a.eml", "a.pdf", "a.docx"] {
+                documents::scan_document(file, input.as_bytes());
+            }
+        }
+
+        #[test]
+        fn no_iban_like_input_panics(input in "[A-ZÖa-z]{2}[0-9]{2}[0-9A-Zß ]{0,30}") {
+            get_iban_vo_from_str(&input);
+            partial::get_partial_iban_vo_from_str(&input);
+            let _ = explain::explain_checksum(&input);
+            let _ = swiss_qr::is_qr_iban(&input);
+            let _ = input.parse::<iban::Iban>().map(|iban| (iban.to_string(), iban.bank_identifier().map(str::to_string), iban.qr_iid()));
+            text_scan::find_ibans(&input);
+        }
+
+        #[test]
+        fn no_payment_payload_panics(fields in proptest::collection::vec("[ -~ÖÄ]{0,12}", 31)) {
+            let _ = epc_qr::parse_payload(&(String::from("BCD\n") + &fields[..11].join("\n")));
+            let mut fields = fields;
+            fields[0] = String::from("SPC");
+            fields[30] = String::from("EPD");
+            let _ = swiss_qr::parse_and_validate(&fields.join("\n"));
+        }
+    }
+}
@@ -3,237 +3,252 @@
     windows_subsystem = "windows"
 )]
 
-use serde::ser::{Serialize, Serializer, SerializeStruct};
-
-mod country_data;
-
-#[derive(PartialEq, Debug)]
-struct IbanVO {
-    iban: String,
-    is_alphanumeric: bool,
-    is_valid_country: bool,
-    is_correct_length: bool,
-    is_divisible_by_97: bool
-}
-
-impl Serialize for IbanVO {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut state = serializer.serialize_struct("IbanVO", 2)?;
-        state.serialize_field("iban", &self.iban)?;
-        state.serialize_field("isValidCountry", &self.is_valid_country)?;
-        state.serialize_field("isAlphanumeric", &self.is_alphanumeric)?;
-        state.serialize_field("isCorrectLength", &self.is_correct_length)?;
-        state.serialize_field("isDivisibleBy97", &self.is_divisible_by_97)?;
-        state.end()
+use iban_validator_lib::{
+    bundesbank, cli, credit_transfer, creditor_id, csv_input, documents, domestic_account, domestic_notation, epc_qr, explain,
+    generate, get_iban_vo_with, iso20022, iso7064, lei, national_check, partial, pseudonymize, qr_decode,
+    references, rf_reference, swift_mt, swiss_qr, text_scan, vocalink, BankFiles, IbanError, IbanVO,
+};
+
+#[tauri::command]
+fn validate_iban(iban_numbers: Vec<&str>) -> Result<Vec<IbanVO>, IbanError> {
+    if iban_numbers.is_empty() {
+        return Err(IbanError::NoIbans);
     }
+    let bank_files = BankFiles::loaded();
+    Ok(iban_numbers.into_iter().map(|iban| get_iban_vo_with(iban, &bank_files)).collect())
 }
 
-fn is_alphanumeric(iban: &str) -> bool {
-    iban.chars().all(|c| {
-        c.is_alphanumeric()
-    })
+#[tauri::command]
+fn find_ibans_in_text(text: &str) -> Vec<text_scan::IbanMatch> {
+    text_scan::find_ibans(text)
 }
 
+#[tauri::command]
+fn scan_documents(paths: Vec<&str>) -> Vec<documents::DocumentScan> {
+    paths
+        .into_iter()
+        .flat_map(|path| documents::scan_file(std::path::Path::new(path)))
+        .collect()
+}
 
-fn replace_letters_with_numbers(iban: &str) -> String {
-    iban.to_uppercase().chars().map(|c| {
-        c.to_digit(36).unwrap().to_string()
-    }).collect::<String>()
+#[tauri::command]
+fn validate_partial_iban(iban: &str) -> partial::PartialIbanVO {
+    partial::get_partial_iban_vo_from_str(iban)
 }
 
-fn divide_by_97(iban: &str) -> bool {
-    match iban.parse::<u128>() {
-        Ok(n) => {
-            (n % 97) == 1
-        } 
-        _ => false
-    }
+#[tauri::command]
+fn explain_iban_checksum(iban: &str) -> Result<explain::ChecksumExplanation, explain::ExplainError> {
+    explain::explain_checksum(iban)
 }
 
-fn get_iban_vo_from_str(iban: &str) -> IbanVO {
-    let mut iban_vo = IbanVO {
-        iban: String::from(iban),
-        is_alphanumeric: false,
-        is_valid_country: false,
-        is_correct_length: false,
-        is_divisible_by_97: false,
-    };
+#[tauri::command]
+fn pseudonymize_iban(iban: &str, key: &str, keep_bank_identifier: bool) -> Result<String, pseudonymize::PseudonymizeError> {
+    pseudonymize::pseudonymize(iban, key.as_bytes(), keep_bank_identifier)
+}
 
-    if iban.len() < 2 {
-        // Sanity check so program won't crash in case the string length is less 2
-        return iban_vo;
-    }
-     let is_iban_alphanumeric = is_alphanumeric(&iban);
-    if !is_iban_alphanumeric {
-        return iban_vo;
-    } else {
-        iban_vo.is_alphanumeric = true;
-    }
-    // Get country code from iban string
-    let country_code: &str = &iban[0..2];
+#[tauri::command]
+fn generate_ibans(options: generate::GenerateOptions, count: usize) -> Result<Vec<String>, generate::GenerateError> {
+    generate::generate_ibans(&options, count)
+}
 
-    // Lookup valid country codes, and look up valid length per country
-    let country_codes_and_length = country_data::get_country_codes_and_length();
-    
-    // Has valid country code
-    iban_vo.is_valid_country = country_codes_and_length.get(country_code).is_some();
+#[tauri::command]
+fn load_bank_code_file(path: &str) -> Result<usize, bundesbank::BundesbankError> {
+    bundesbank::load_bank_directory(std::path::Path::new(path))
+}
 
-    let country_length = country_codes_and_length.get(country_code);
-    iban_vo.is_correct_length = country_length == Some(&iban.len());
+#[tauri::command]
+fn german_account_to_iban(blz: &str, account_number: &str) -> Result<String, bundesbank::BundesbankError> {
+    bundesbank::bank_directory()?.to_iban(blz, account_number)
+}
 
-    // move the first four characters to the end of the string
-    let iban = iban[4..].to_string() + &iban[..4];
+#[tauri::command]
+fn load_modulus_file(path: &str) -> Result<usize, vocalink::VocalinkError> {
+    vocalink::load_modulus_file(std::path::Path::new(path))
+}
 
-    // replace each letter with number
-    let iban = replace_letters_with_numbers(&iban);
+#[tauri::command]
+fn check_uk_account(sort_code: &str, account_number: &str) -> Result<national_check::AccountCheck, vocalink::VocalinkError> {
+    Ok(vocalink::modulus_tables()?.check_account(sort_code, account_number))
+}
 
-    // Is IBAN divisible by 97
-    iban_vo.is_divisible_by_97 = divide_by_97(&iban);
-    return iban_vo
+#[tauri::command]
+fn domestic_to_iban(country_code: &str, account: &str) -> Result<String, domestic_notation::NotationError> {
+    domestic_notation::to_iban(country_code, account)
+}
 
+#[tauri::command]
+fn iban_to_domestic(iban: &str) -> Result<String, domestic_notation::NotationError> {
+    domestic_notation::to_domestic(iban)
 }
 
+#[tauri::command]
+fn generate_epc_qr(payment: epc_qr::EpcPayment, format: epc_qr::ImageFormat) -> Result<String, epc_qr::EpcError> {
+    let payload = payment.to_payload()?;
+    epc_qr::render(&payload, format)
+}
 
-#[tauri::command] 
-fn validate_iban(iban_numbers: Vec<&str>) -> Vec<IbanVO> {
+#[tauri::command]
+fn decode_payment_qr(path: &str) -> Result<Vec<qr_decode::DecodedQrCode>, qr_decode::QrDecodeError> {
+    qr_decode::decode_image(std::path::Path::new(path))
+}
 
-    let mut iban_results: Vec<IbanVO> = Vec::new();
-    if iban_numbers.len() > 1 {
-        for iban in iban_numbers.into_iter() {
-            iban_results.push(get_iban_vo_from_str(&iban))
-        };
-    } else {
-        let first_number = iban_numbers.first().unwrap();
-        iban_results.push(get_iban_vo_from_str(&first_number));
-    }
-    
-    return iban_results
+#[tauri::command]
+fn validate_swiss_qr_bill(payload: &str) -> Result<swiss_qr::SwissQrReport, swiss_qr::SwissQrError> {
+    swiss_qr::parse_and_validate(payload)
+}
 
+#[tauri::command]
+fn validate_rf_reference(references: Vec<&str>) -> Vec<rf_reference::RfReferenceVO> {
+    references
+        .into_iter()
+        .map(rf_reference::get_rf_reference_vo_from_str)
+        .collect()
 }
 
-fn main() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![validate_iban])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");    
+#[tauri::command]
+fn generate_rf_reference(base: &str) -> Result<String, rf_reference::RfReferenceError> {
+    rf_reference::RfReference::generate(base).map(|reference| reference.to_string())
 }
 
+#[tauri::command]
+fn validate_payment_reference(reference: &str, iban: Option<&str>) -> references::ReferenceVO {
+    references::get_reference_vo(reference, iban)
+}
 
+#[tauri::command]
+fn generate_payment_reference(
+    scheme: references::ReferenceScheme,
+    base: &str,
+) -> Result<String, references::ReferenceError> {
+    references::generate(scheme, base)
+}
 
+#[tauri::command]
+fn validate_creditor_id(creditor_ids: Vec<&str>) -> Vec<creditor_id::CreditorIdVO> {
+    creditor_ids
+        .into_iter()
+        .map(creditor_id::get_creditor_id_vo_from_str)
+        .collect()
+}
 
-// Tests: Placing unit tests in the same file is the idiomatic way of doing it in rust
-#[cfg(test)] // Do not include this in the executable
-mod tests {   
+#[tauri::command]
+fn generate_creditor_id(country_code: &str, business_code: &str, national_identifier: &str) -> Option<String> {
+    creditor_id::generate_creditor_id(country_code, business_code, national_identifier)
+}
 
-    use super::*; // Brings in all methods in this file in the tests scope
+#[tauri::command]
+fn compute_check_characters(system: iso7064::System, input: &str) -> Result<String, iso7064::Iso7064Error> {
+    system.compute(&input.to_uppercase())
+}
 
-    #[test]
-    fn test_letter_replacer() {
-        let iban = "DE89370400440532013000";
-        let expected = "131489370400440532013000";
-        assert_eq!(replace_letters_with_numbers(iban), expected);
-
-        let iban_two = "89370400440532013DE000";
-        let expected_two = "893704004405320131314000";
-        assert_eq!(replace_letters_with_numbers(iban_two), expected_two);
-    }
+#[tauri::command]
+fn verify_check_characters(system: iso7064::System, input: &str) -> bool {
+    system.verify(&input.to_uppercase())
+}
 
-    #[test]
-    fn test_valid_iban() {
-        let iban1 = "BH02CITI00001077181611";
-        let iban2 = "DJ2110002010010409943020008";
-        let iban3 = "SV43ACAT00000000000000123123";
-        let iban4 = "GT20AGRO00000000001234567890";
-        let with_non_alphanumeric_char = "!GT20AGRO00000000001234567890";
-
-        assert_eq!(get_iban_vo_from_str(iban1), IbanVO {
-            iban: String::from(iban1),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: true,
-            is_divisible_by_97: true,
-        });
-        assert_eq!(get_iban_vo_from_str(iban2), IbanVO {
-            iban: String::from(iban2),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: true,
-            is_divisible_by_97: true,
-        });
-        assert_eq!(get_iban_vo_from_str(iban3), IbanVO {
-            iban: String::from(iban3),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: true,
-            is_divisible_by_97: true,
-        });
-        assert_eq!(get_iban_vo_from_str(iban4), IbanVO {
-            iban: String::from(iban4),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: true,
-            is_divisible_by_97: true,
-        });
-        assert_eq!(get_iban_vo_from_str(with_non_alphanumeric_char), IbanVO {
-            iban: String::from(with_non_alphanumeric_char),
-            is_alphanumeric: false,
-            is_valid_country: false,
-            is_correct_length: false,
-            is_divisible_by_97: false,
-        });
-    }
+#[tauri::command]
+fn validate_lei(leis: Vec<&str>) -> Vec<lei::LeiVO> {
+    leis.into_iter().map(lei::get_lei_vo_from_str).collect()
+}
 
-    #[test]
-    fn test_not_divisible_by_97() {
-        let iban = "DE89370400440532013001";
-        let expected_output = IbanVO {
-            iban: String::from(iban),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: true,
-            is_divisible_by_97: false,
-        };
-        assert_eq!(get_iban_vo_from_str(iban), expected_output);
-    }
+#[tauri::command]
+fn validate_domestic_account(country_code: &str, accounts: Vec<&str>) -> Vec<domestic_account::DomesticAccountVO> {
+    accounts
+        .into_iter()
+        .map(|account| domestic_account::get_domestic_account_vo_from_str(country_code, account))
+        .collect()
+}
 
-    #[test]
-    fn test_is_alphanumeric() {
-        let num1 = "LKSDJFLKJHLKJ39872389476";
-        assert_eq!(is_alphanumeric(num1), true);
-        let num2 = "ASDÖLKölsdgjlknqåeptoilkbxöcbjewrjiqopwer923874013658971";
-        assert_eq!(is_alphanumeric(num2), true);
-        let num3 = "ABC_";
-        assert_eq!(is_alphanumeric(num3), false);
-        let num4 = "ABC;";
-        assert_eq!(is_alphanumeric(num4), false);
-    }
+#[tauri::command]
+fn generate_lei(base: &str) -> Option<String> {
+    lei::generate_lei(base)
+}
 
-    #[test]
-    fn test_incorrect_length() {
-        let iban = "AT4832000000123234245864";
-        let expected_output = IbanVO {
-            iban: String::from(iban),
-            is_alphanumeric: true,
-            is_valid_country: true,
-            is_correct_length: false,
-            is_divisible_by_97: false,
-        };
-        assert_eq!(get_iban_vo_from_str(iban), expected_output);
+#[tauri::command]
+fn validate_csv(
+    contents: &str,
+    iban_column: Option<&str>,
+    lei_column: Option<&str>,
+    reference_column: Option<&str>,
+) -> Result<Vec<csv_input::CsvRowResult>, csv_input::CsvError> {
+    csv_input::validate_csv(contents, iban_column, lei_column, reference_column)
+}
+
+#[tauri::command]
+fn validate_payment_message(xml: &str) -> Result<iso20022::MessageReport, iso20022::Iso20022Error> {
+    iso20022::validate_message(xml)
+}
+
+#[tauri::command]
+fn create_credit_transfer(
+    contents: &str,
+    options: credit_transfer::CreditTransferOptions,
+) -> Result<credit_transfer::CreditTransferFile, credit_transfer::CreditTransferError> {
+    credit_transfer::create_credit_transfer(contents, &options)
+}
+
+#[tauri::command]
+fn scan_swift_messages(contents: &str) -> Result<Vec<swift_mt::MtMessage>, swift_mt::SwiftMtError> {
+    swift_mt::scan_messages(contents, &BankFiles::loaded())
+}
+
+fn main() {
+    // A mode as the first argument runs the command line interface instead of opening the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| cli::is_mode(arg)) {
+        std::process::exit(cli::run(&args));
     }
 
+    tauri::Builder::default()
+        .invoke_handler(tauri::generate_handler![
+            validate_iban,
+            find_ibans_in_text,
+            scan_documents,
+            validate_partial_iban,
+            explain_iban_checksum,
+            pseudonymize_iban,
+            generate_ibans,
+            load_bank_code_file,
+            german_account_to_iban,
+            load_modulus_file,
+            check_uk_account,
+            domestic_to_iban,
+            iban_to_domestic,
+            generate_epc_qr,
+            decode_payment_qr,
+            validate_swiss_qr_bill,
+            validate_rf_reference,
+            generate_rf_reference,
+            validate_payment_reference,
+            generate_payment_reference,
+            validate_creditor_id,
+            generate_creditor_id,
+            compute_check_characters,
+            verify_check_characters,
+            validate_lei,
+            generate_lei,
+            validate_domestic_account,
+            validate_csv,
+            validate_payment_message,
+            create_credit_transfer,
+            scan_swift_messages
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");    
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
     #[test]
-    fn test_invalid_country_code() {
-        let iban = "XX89370400440532013000";
-        let expected_output = IbanVO {
-            iban: String::from(iban),
-            is_alphanumeric: true,
-            is_valid_country: false,
-            is_correct_length: false,
-            is_divisible_by_97: false,
-        };
-        assert_eq!(get_iban_vo_from_str(iban), expected_output);
+    fn test_validate_iban_errors() {
+        assert_eq!(validate_iban(vec![]), Err(IbanError::NoIbans));
+        assert_eq!(
+            serde_json::to_string(&IbanError::InvalidCharacter('Ö')).unwrap(),
+            r#"{"kind":"InvalidCharacter","message":"'Ö' is not a letter A-Z or a digit"}"#
+        );
+        assert_eq!(validate_iban(vec!["DE89370400440532013000", "XX"]).unwrap().len(), 2);
     }
 }
@@ -0,0 +1,208 @@
+// National check digits inside the BBAN. Many countries had check digits in their account
+// numbers before the IBAN, and an IBAN with wrong national check digits is rejected by the banks
+// of the country even though its IBAN check digits are correct.
+use std::ops::Range;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::iso7064;
+
+// The countries with national check digits that can be checked
+pub const COUNTRIES: [&str; 14] = ["BE", "FR", "MC", "ES", "IT", "SM", "NO", "FI", "PT", "SI", "ME", "RS", "MK", "BA"];
+
+// The result of checking the account number in an IBAN against its bank, for the countries where
+// the banks publish how their account numbers are checked
+#[derive(Clone, PartialEq, Debug)]
+pub struct AccountCheck {
+    pub bank_code: String,
+    pub account_number: String,
+    pub bank_name: Option<String>,
+    pub bic: Option<String>,
+    pub method: Option<String>,
+    // `None` if the bank is unknown or its method is not implemented
+    pub is_valid: Option<bool>,
+}
+
+impl Serialize for AccountCheck {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("AccountCheck", 6)?;
+        state.serialize_field("bankCode", &self.bank_code)?;
+        state.serialize_field("accountNumber", &self.account_number)?;
+        state.serialize_field("bankName", &self.bank_name)?;
+        state.serialize_field("bic", &self.bic)?;
+        state.serialize_field("method", &self.method)?;
+        state.serialize_field("isValid", &self.is_valid)?;
+        state.end()
+    }
+}
+
+fn digits_value(digits: &str) -> Option<u64> {
+    digits.parse().ok()
+}
+
+// Belgium: the first 10 digits modulo 97, where 0 is written as 97
+fn belgian(bban: &str) -> Option<String> {
+    let check = digits_value(bban.get(0..10)?)? % 97;
+    Some(format!("{:02}", if check == 0 { 97 } else { check }))
+}
+
+// France and Monaco: the RIB key over the bank, branch and account number, where the letters of
+// the account number count as digits A, J = 1, B, K, S = 2, ..., I, R, Z = 9
+fn french(bban: &str) -> Option<String> {
+    let digits: String = bban
+        .get(0..21)?
+        .chars()
+        .map(|c| match c {
+            'A'..='I' => char::from_digit(c as u32 - 'A' as u32 + 1, 10),
+            'J'..='R' => char::from_digit(c as u32 - 'J' as u32 + 1, 10),
+            'S'..='Z' => char::from_digit(c as u32 - 'S' as u32 + 2, 10),
+            '0'..='9' => Some(c),
+            _ => None,
+        })
+        .collect::<Option<String>>()?;
+    let sum = 89 * digits_value(&digits[0..5])? + 15 * digits_value(&digits[5..10])? + 3 * digits_value(&digits[10..21])?;
+    Some(format!("{:02}", 97 - sum % 97))
+}
+
+fn spanish_digit(digits: &str) -> Option<u32> {
+    const WEIGHTS: [u32; 10] = [1, 2, 4, 8, 5, 10, 9, 7, 3, 6];
+    let sum = digits
+        .chars()
+        .zip(WEIGHTS)
+        .map(|(c, weight)| c.to_digit(10).map(|digit| digit * weight))
+        .sum::<Option<u32>>()?;
+    Some(match 11 - sum % 11 {
+        11 => 0,
+        10 => 1,
+        check => check,
+    })
+}
+
+// Spain: one digit for the bank and branch, and one for the account number
+fn spanish(bban: &str) -> Option<String> {
+    let first = spanish_digit(&format!("00{}", bban.get(0..8)?))?;
+    let second = spanish_digit(bban.get(10..20)?)?;
+    Some(format!("{}{}", first, second))
+}
+
+// Italy and San Marino: the CIN letter in front of the bank, branch and account number
+fn italian(bban: &str) -> Option<String> {
+    const ODD: [u32; 26] = [
+        1, 0, 5, 7, 9, 13, 15, 17, 19, 21, 2, 4, 18, 20, 11, 3, 6, 8, 12, 14, 16, 10, 22, 25, 24, 23,
+    ];
+    let mut sum = 0;
+    for (i, c) in bban.get(1..23)?.chars().enumerate() {
+        let value = match c {
+            '0'..='9' => c as u32 - '0' as u32,
+            'A'..='Z' => c as u32 - 'A' as u32,
+            _ => return None,
+        };
+        // Positions count from 1, the odd positions are converted with the table
+        sum += if i % 2 == 0 { ODD[value as usize] } else { value };
+    }
+    char::from_u32('A' as u32 + sum % 26).map(String::from)
+}
+
+// Norway: weighted modulo 11, account numbers that would need the check digit 10 aren't issued
+fn norwegian(bban: &str) -> Option<String> {
+    const WEIGHTS: [u32; 10] = [5, 4, 3, 2, 7, 6, 5, 4, 3, 2];
+    let sum = bban
+        .get(0..10)?
+        .chars()
+        .zip(WEIGHTS)
+        .map(|(c, weight)| c.to_digit(10).map(|digit| digit * weight))
+        .sum::<Option<u32>>()?;
+    match 11 - sum % 11 {
+        11 => Some(String::from("0")),
+        10 => None,
+        check => Some(check.to_string()),
+    }
+}
+
+// Finland: the Luhn check digit
+fn finnish(bban: &str) -> Option<String> {
+    let mut sum = 0;
+    for (i, c) in bban.get(0..13)?.chars().rev().enumerate() {
+        let digit = c.to_digit(10)? * if i % 2 == 0 { 2 } else { 1 };
+        sum += if digit > 9 { digit - 9 } else { digit };
+    }
+    Some(((10 - sum % 10) % 10).to_string())
+}
+
+// Portugal and the former Yugoslav countries: ISO 7064 MOD 97-10 over the rest of the BBAN
+fn mod97_10(bban: &str, length: usize) -> Option<String> {
+    iso7064::System::Mod97_10.compute(bban.get(0..length - 2)?).ok()
+}
+
+// The position of the national check digits in the BBAN, and their correct value for the rest of
+// the BBAN. `None` for countries without national check digits, or if the BBAN can't be checked.
+pub fn national_check_digits(country_code: &str, bban: &str) -> Option<(Range<usize>, String)> {
+    let (range, check) = match country_code {
+        "BE" => (10..12, belgian(bban)),
+        "FR" | "MC" => (21..23, french(bban)),
+        "ES" => (8..10, spanish(bban)),
+        "IT" | "SM" => (0..1, italian(bban)),
+        "NO" => (10..11, norwegian(bban)),
+        "FI" => (13..14, finnish(bban)),
+        "PT" => (19..21, mod97_10(bban, 21)),
+        "SI" => (13..15, mod97_10(bban, 15)),
+        "ME" | "RS" => (16..18, mod97_10(bban, 18)),
+        "MK" => (13..15, mod97_10(bban, 15)),
+        "BA" => (14..16, mod97_10(bban, 16)),
+        _ => return None,
+    };
+    if bban.len() < range.end {
+        return None;
+    }
+    check.map(|check| (range, check))
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    // Whether the national check digits are correct, `None` for countries without them
+    pub fn is_valid_national(country_code: &str, bban: &str) -> Option<bool> {
+        if !COUNTRIES.contains(&country_code) {
+            return None;
+        }
+        Some(national_check_digits(country_code, bban).is_some_and(|(range, check)| bban.get(range) == Some(check.as_str())))
+    }
+
+    #[test]
+    fn test_valid_national_check_digits() {
+        for iban in [
+            "BE68539007547034",
+            "FR1420041010050500013M02606",
+            "ES9121000418450200051332",
+            "IT60X0542811101000000123456",
+            "NO9386011117947",
+            "FI2112345600000785",
+            "PT50000201231234567890154",
+            "SI56263300012039086",
+            "ME25505000012345678951",
+            "RS35260005601001611379",
+            "MK07250120000058984",
+            "BA391290079401028494",
+        ] {
+            assert_eq!(is_valid_national(&iban[0..2], &iban[4..]), Some(true), "{}", iban);
+        }
+    }
+
+    #[test]
+    fn test_invalid_national_check_digits() {
+        assert_eq!(is_valid_national("BE", "539007547035"), Some(false));
+        assert_eq!(is_valid_national("ES", "21000418460200051332"), Some(false));
+        assert_eq!(is_valid_national("IT", "Y0542811101000000123456"), Some(false));
+        assert_eq!(is_valid_national("BE", "5390"), Some(false));
+        assert_eq!(is_valid_national("DE", "370400440532013000"), None);
+        assert_eq!(
+            national_check_digits("FR", "20041010050500013M02600"),
+            Some((21..23, String::from("06")))
+        );
+    }
+}
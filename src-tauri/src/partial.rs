@@ -0,0 +1,165 @@
+// Validation of an IBAN while it is being typed. Every character is checked against what can
+// come at its position: the country code, check digits 02 to 98, and the BBAN structure of the
+// country, so a mistake is reported at the character where it is made.
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::country_data::{self, CharacterType};
+use crate::get_iban_vo_from_str;
+
+#[derive(PartialEq, Debug)]
+pub struct PartialIbanVO {
+    pub iban: String,
+    pub can_become_valid: bool,
+    pub is_complete: bool,
+    // Positions count from 0, ignoring whitespace. A complete IBAN with wrong check digits has no
+    // invalid position, but cannot become valid.
+    pub first_invalid_position: Option<usize>,
+    pub expected_length: Option<usize>,
+    pub expected_next: Option<CharacterType>,
+}
+
+impl Serialize for PartialIbanVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("PartialIbanVO", 6)?;
+        state.serialize_field("iban", &self.iban)?;
+        state.serialize_field("canBecomeValid", &self.can_become_valid)?;
+        state.serialize_field("isComplete", &self.is_complete)?;
+        state.serialize_field("firstInvalidPosition", &self.first_invalid_position)?;
+        state.serialize_field("expectedLength", &self.expected_length)?;
+        state.serialize_field("expectedNext", &self.expected_next)?;
+        state.end()
+    }
+}
+
+fn expected_type(position: usize, country_code: &str) -> CharacterType {
+    match position {
+        0 | 1 => CharacterType::A,
+        2 | 3 => CharacterType::N,
+        _ => {
+            let mut offset = position - 4;
+            for &(length, character_type) in country_data::get_bban_structure(country_code).unwrap_or(&[]) {
+                if offset < length {
+                    return character_type;
+                }
+                offset -= length;
+            }
+            // Countries without a known structure accept any letter or digit
+            CharacterType::C
+        }
+    }
+}
+
+// Whether the character at the position can be followed by more characters and still make a valid IBAN
+fn is_valid_at(iban: &str, position: usize, c: char) -> bool {
+    let country_codes_and_length = country_data::get_country_codes_and_length();
+    match position {
+        0 => country_codes_and_length
+            .keys()
+            .chain(country_data::TERRITORIES.iter().map(|(territory, _)| territory))
+            .any(|code| code.starts_with(c)),
+        1 => country_codes_and_length.contains_key(country_data::get_format_country(&iban[0..2])),
+        // Check digits are 02 to 98
+        3 => !matches!(&iban[2..4], "00" | "01" | "99"),
+        _ => true,
+    }
+}
+
+pub fn get_partial_iban_vo_from_str(iban: &str) -> PartialIbanVO {
+    let iban: String = iban.split_whitespace().collect();
+    let country_code = country_data::get_format_country(iban.get(0..2).unwrap_or(""));
+    let expected_length = country_data::get_country_codes_and_length()
+        .get(country_code)
+        .copied();
+
+    let first_invalid_position = iban.chars().enumerate().position(|(position, c)| {
+        !expected_type(position, country_code).accepts(c)
+            || expected_length.is_some_and(|length| position >= length)
+            || !is_valid_at(&iban, position, c)
+    });
+
+    let is_complete = first_invalid_position.is_none() && expected_length == Some(iban.len());
+    let can_become_valid = match first_invalid_position {
+        Some(_) => false,
+        None => !is_complete || get_iban_vo_from_str(&iban).is_valid(),
+    };
+    let expected_next = Some(expected_type(iban.len(), country_code))
+        .filter(|_| first_invalid_position.is_none() && !is_complete);
+
+    PartialIbanVO {
+        iban,
+        can_become_valid,
+        is_complete,
+        first_invalid_position,
+        expected_length,
+        expected_next,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_prefixes_of_valid_iban() {
+        let iban = "DE89370400440532013000";
+        for length in 0..iban.len() {
+            let partial = get_partial_iban_vo_from_str(&iban[..length]);
+            assert!(partial.can_become_valid, "{}", &iban[..length]);
+            assert!(!partial.is_complete);
+            assert_eq!(partial.first_invalid_position, None);
+        }
+        assert_eq!(
+            get_partial_iban_vo_from_str("DE89 3704"),
+            PartialIbanVO {
+                iban: String::from("DE893704"),
+                can_become_valid: true,
+                is_complete: false,
+                first_invalid_position: None,
+                expected_length: Some(22),
+                expected_next: Some(CharacterType::N),
+            }
+        );
+        let complete = get_partial_iban_vo_from_str(iban);
+        assert!(complete.is_complete && complete.can_become_valid);
+        assert_eq!(complete.expected_next, None);
+    }
+
+    #[test]
+    fn test_expected_next() {
+        assert_eq!(get_partial_iban_vo_from_str("").expected_next, Some(CharacterType::A));
+        assert_eq!(get_partial_iban_vo_from_str("GB").expected_next, Some(CharacterType::N));
+        assert_eq!(get_partial_iban_vo_from_str("GB29").expected_next, Some(CharacterType::A));
+        assert_eq!(get_partial_iban_vo_from_str("GB29NWBK").expected_next, Some(CharacterType::N));
+        assert_eq!(get_partial_iban_vo_from_str("MT84MALT01100").expected_next, Some(CharacterType::C));
+        // Jersey uses the format of GB
+        assert_eq!(get_partial_iban_vo_from_str("JE90NWBK").expected_next, Some(CharacterType::N));
+        assert_eq!(get_partial_iban_vo_from_str("JE").expected_length, Some(22));
+    }
+
+    #[test]
+    fn test_first_invalid_position() {
+        assert_eq!(get_partial_iban_vo_from_str("1").first_invalid_position, Some(0));
+        assert_eq!(get_partial_iban_vo_from_str("DX").first_invalid_position, Some(1));
+        assert_eq!(get_partial_iban_vo_from_str("DE8A").first_invalid_position, Some(3));
+        assert_eq!(get_partial_iban_vo_from_str("DE99").first_invalid_position, Some(3));
+        assert_eq!(get_partial_iban_vo_from_str("GB29NW1K").first_invalid_position, Some(6));
+        assert_eq!(get_partial_iban_vo_from_str("DE893704004405320130000").first_invalid_position, Some(22));
+
+        let partial = get_partial_iban_vo_from_str("DE89 37O4");
+        assert!(!partial.can_become_valid);
+        assert_eq!(partial.first_invalid_position, Some(6));
+        assert_eq!(partial.expected_next, None);
+    }
+
+    #[test]
+    fn test_complete_with_wrong_check_digits() {
+        let partial = get_partial_iban_vo_from_str("DE89370400440532013001");
+        assert!(partial.is_complete);
+        assert!(!partial.can_become_valid);
+        assert_eq!(partial.first_invalid_position, None);
+    }
+}
@@ -0,0 +1,267 @@
+// Pseudonymization of IBANs for test environments. A valid IBAN is mapped to another valid IBAN of
+// the same country, with the same letters and digits structure, and with correct IBAN and
+// national check digits. The mapping is keyed and deterministic, so the same IBAN gets the same
+// pseudonym in every table it occurs in. The mapping is a permutation, so different IBANs never get
+// the same pseudonym. With the bank code file or the Vocalink modulus file passed to
+// `pseudonymize_with`, German and UK pseudonyms pass the method of their bank as well, and then
+// depend on the files. An account number that fails the method itself can share its pseudonym with
+// another account, it isn't covered by the permutation.
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use serde::ser::{Serialize, Serializer};
+use sha2::Sha256;
+
+use crate::country_data::{self, CharacterType};
+use crate::{generate, get_iban_vo_from_str, get_iban_vo_with, iso7064, national_check, BankFiles};
+
+// The rounds of the Feistel network, as many as FF1 of NIST SP 800-38G
+const ROUNDS: u8 = 10;
+
+// Pseudonyms without national check digits, and German and UK accounts that fail the method of
+// their bank, are encrypted again until one fits. A few attempts are enough for almost all IBANs
+const MAX_ATTEMPTS: u32 = 1000;
+
+#[derive(PartialEq, Debug)]
+pub enum PseudonymizeError {
+    InvalidIban(String),
+    NoPseudonym(String),
+}
+
+impl fmt::Display for PseudonymizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PseudonymizeError::InvalidIban(iban) => write!(f, "{} is not a valid IBAN", iban),
+            PseudonymizeError::NoPseudonym(iban) => write!(f, "no pseudonym could be made for {}", iban),
+        }
+    }
+}
+
+impl Serialize for PseudonymizeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+// Countries without a known structure keep digits as digits and letters as letters
+fn character_types(country_code: &str, bban: &str) -> Vec<CharacterType> {
+    country_data::get_bban_character_types(country_code).unwrap_or_else(|| {
+        bban.chars()
+            .map(|c| if c.is_ascii_digit() { CharacterType::N } else { CharacterType::A })
+            .collect()
+    })
+}
+
+// The position of a character in the alphabet of its type
+fn digit_value(character_type: CharacterType, c: char) -> u16 {
+    let value = c.to_digit(36).unwrap_or(0) as u16;
+    match character_type {
+        CharacterType::A => value.saturating_sub(10),
+        _ => value,
+    }
+}
+
+fn radix(character_type: CharacterType) -> u16 {
+    match character_type {
+        CharacterType::N => 10,
+        CharacterType::A => 26,
+        CharacterType::C => 36,
+    }
+}
+
+// A keyed permutation of the free characters, a Feistel network that adds the round function to
+// one half, character by character modulo the size of its alphabet, and alternates the halves.
+// Every round can be undone, so two IBANs never get the same pseudonym. The tweak, the country code
+// and the kept characters, are the same for every IBAN that can have the pseudonym
+fn encrypt(key: &[u8], tweak: &str, values: &mut [u16], radices: &[u16]) {
+    let half = values.len() / 2;
+    for round in 0..ROUNDS {
+        let (target, source) = if round % 2 == 0 { (0..half, half..values.len()) } else { (half..values.len(), 0..half) };
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(&[round]);
+        mac.update(tweak.as_bytes());
+        for &value in &values[source] {
+            mac.update(&value.to_be_bytes());
+        }
+        // At most 15 characters in a half of the longest BBAN, two bytes of the HMAC for each
+        let bytes = mac.finalize().into_bytes();
+        for (i, position) in target.enumerate() {
+            let addend = u16::from_be_bytes([bytes[2 * i], bytes[2 * i + 1]]) % radices[position];
+            values[position] = (values[position] + addend) % radices[position];
+        }
+    }
+}
+
+// The pseudonym without the checks of the bank files, the same whatever files are loaded
+pub fn pseudonymize(iban: &str, key: &[u8], keep_bank_identifier: bool) -> Result<String, PseudonymizeError> {
+    pseudonymize_with(iban, key, keep_bank_identifier, &BankFiles::default())
+}
+
+pub fn pseudonymize_with(
+    iban: &str,
+    key: &[u8],
+    keep_bank_identifier: bool,
+    bank_files: &BankFiles,
+) -> Result<String, PseudonymizeError> {
+    let iban: String = iban.split_whitespace().collect::<String>().to_uppercase();
+    if !get_iban_vo_from_str(&iban).is_valid() {
+        return Err(PseudonymizeError::InvalidIban(iban));
+    }
+    let (country_code, bban) = (&iban[0..2], &iban[4..]);
+    let format_country_code = country_data::get_format_country(country_code);
+    let types = character_types(format_country_code, bban);
+    let kept = country_data::get_bank_identifier_range(format_country_code).filter(|_| keep_bank_identifier);
+    // The national check digits follow from the other characters, so they are left out of the
+    // permutation and calculated afterwards
+    let check_range = if national_check::COUNTRIES.contains(&format_country_code) {
+        match national_check::national_check_digits(format_country_code, bban) {
+            Some((range, _)) => Some(range),
+            None => return Err(PseudonymizeError::NoPseudonym(iban)),
+        }
+    } else {
+        None
+    };
+    let is_kept = |i: &usize| kept.as_ref().is_some_and(|range| range.contains(i));
+    let is_free = |i: &usize| !is_kept(i) && !check_range.as_ref().is_some_and(|range| range.contains(i));
+
+    let mut pseudonym: Vec<char> = bban.chars().collect();
+    let free: Vec<usize> = (0..pseudonym.len()).filter(is_free).collect();
+    let radices: Vec<u16> = free.iter().map(|&i| radix(types[i])).collect();
+    let mut values: Vec<u16> = free.iter().map(|&i| digit_value(types[i], pseudonym[i])).collect();
+    let tweak: String = country_code
+        .chars()
+        .chain(pseudonym.iter().enumerate().filter(|(i, _)| is_kept(i)).map(|(_, &c)| c))
+        .collect();
+
+    for _ in 0..MAX_ATTEMPTS {
+        encrypt(key, &tweak, &mut values, &radices);
+        for (&i, &value) in free.iter().zip(values.iter()) {
+            pseudonym[i] = generate::random_char(types[i], value as u8);
+        }
+        let mut candidate: String = pseudonym.iter().collect();
+        if check_range.is_some() {
+            match national_check::national_check_digits(format_country_code, &candidate) {
+                Some((range, check)) => candidate.replace_range(range, &check),
+                None => continue,
+            }
+        }
+        let check_digits = iso7064::System::Mod97_10
+            .compute(&(candidate.clone() + country_code))
+            .map_err(|_| PseudonymizeError::NoPseudonym(iban.clone()))?;
+        let candidate = format!("{}{}{}", country_code, check_digits, candidate);
+        // With the bank code file or the modulus file, the account number has to pass the method of
+        // its bank as well
+        let account_check = get_iban_vo_with(&candidate, bank_files).national_account_check;
+        if account_check.is_some_and(|check| check.is_valid == Some(false)) {
+            continue;
+        }
+        return Ok(candidate);
+    }
+    Err(PseudonymizeError::NoPseudonym(iban))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bundesbank;
+
+    const IBANS: [&str; 9] = [
+        "DE89370400440532013000",
+        "GB29NWBK60161331926819",
+        "FR1420041010050500013M02606",
+        "IT60X0542811101000000123456",
+        "ES9121000418450200051332",
+        "NO9386011117947",
+        "BE68539007547034",
+        "MT84MALT011000012345MTLCAST001S",
+        "GP1120041010050500013M02606",
+    ];
+
+    #[test]
+    fn test_pseudonyms_are_valid_and_keep_the_structure() {
+        for iban in IBANS {
+            let pseudonym = pseudonymize(iban, b"test key", false).unwrap();
+            assert_ne!(pseudonym, iban);
+            assert!(get_iban_vo_from_str(&pseudonym).is_valid(), "{} -> {}", iban, pseudonym);
+            assert_eq!(&pseudonym[0..2], &iban[0..2]);
+            let types = character_types(country_data::get_format_country(&iban[0..2]), &iban[4..]);
+            assert!(pseudonym[4..].chars().zip(types).all(|(c, character_type)| character_type.accepts(c)));
+            let format_country_code = country_data::get_format_country(&pseudonym[0..2]);
+            if let Some(is_valid) = national_check::tests::is_valid_national(format_country_code, &pseudonym[4..]) {
+                assert!(is_valid, "{} -> {}", iban, pseudonym);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pseudonyms_are_deterministic() {
+        assert_eq!(
+            pseudonymize("DE89 3704 0044 0532 0130 00", b"key", false),
+            pseudonymize("DE89370400440532013000", b"key", false)
+        );
+        assert_ne!(
+            pseudonymize("DE89370400440532013000", b"key", false),
+            pseudonymize("DE89370400440532013000", b"other key", false)
+        );
+    }
+
+    #[test]
+    fn test_keep_bank_identifier() {
+        let pseudonym = pseudonymize("GB29NWBK60161331926819", b"key", true).unwrap();
+        assert_eq!(&pseudonym[4..8], "NWBK");
+        let pseudonym = pseudonymize("IT60X0542811101000000123456", b"key", true).unwrap();
+        assert_eq!(&pseudonym[5..10], "05428");
+    }
+
+    #[test]
+    fn test_pseudonyms_do_not_collide() {
+        let options = generate::GenerateOptions {
+            country_code: String::from("NO"),
+            bank_code: Some(String::from("8601")),
+            national_check_digits: true,
+            seed: Some(7),
+        };
+        let ibans: HashSet<String> = generate::generate_ibans(&options, 5000).unwrap().into_iter().collect();
+        let pseudonyms: HashSet<String> =
+            ibans.iter().map(|iban| pseudonymize(iban, b"key", true).unwrap()).collect();
+        assert_eq!(pseudonyms.len(), ibans.len());
+        assert!(pseudonyms.iter().all(|pseudonym| pseudonym.starts_with("NO") && &pseudonym[4..8] == "8601"));
+    }
+
+    #[test]
+    fn test_bank_files_are_passed_in() {
+        let pseudonyms: Vec<_> = [false, true].map(|keep| pseudonymize("DE89370400440532013000", b"key", keep)).into();
+        // A loaded bank directory doesn't change the pseudonyms, only one that is passed in
+        let path = std::env::temp_dir().join(format!("iban-pseudonymize-test-{}.txt", std::process::id()));
+        std::fs::write(&path, bundesbank::tests::directory_file()).unwrap();
+        let loaded = bundesbank::load_bank_directory(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Ok(8));
+        for (keep, pseudonym) in [false, true].into_iter().zip(pseudonyms) {
+            assert_eq!(pseudonymize("DE89370400440532013000", b"key", keep), pseudonym);
+        }
+        let bank_files = BankFiles {
+            bank_directory: Some(Arc::new(bundesbank::tests::directory())),
+            modulus_tables: None,
+        };
+        // The directory only has the bank when the bank code is kept
+        let pseudonym = pseudonymize_with("DE89370400440532013000", b"key", true, &bank_files).unwrap();
+        let check = get_iban_vo_with(&pseudonym, &bank_files).national_account_check.unwrap();
+        assert_eq!(check.is_valid, Some(true), "{}", pseudonym);
+    }
+
+    #[test]
+    fn test_invalid_iban() {
+        assert_eq!(
+            pseudonymize("DE89370400440532013001", b"key", false),
+            Err(PseudonymizeError::InvalidIban(String::from("DE89370400440532013001")))
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// Finds QR codes in invoice screenshots and reads the payment details in them
+use std::fmt;
+use std::path::Path;
+
+use serde::{Serialize, Serializer};
+
+use crate::epc_qr::{self, EpcPayment};
+use crate::swiss_qr::{self, SwissQrBill};
+use crate::{get_iban_vo_from_str, IbanVO};
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(tag = "kind", content = "fields", rename_all = "camelCase")]
+pub enum Payment {
+    Epc(Box<EpcPayment>),
+    SwissQrBill(Box<SwissQrBill>),
+    // A QR code that does not hold a payment we know of
+    Unknown,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedQrCode {
+    pub payload: String,
+    pub payment: Payment,
+    // Only set when the payload contains an IBAN
+    pub validation: Option<IbanVO>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum QrDecodeError {
+    UnreadableImage(String),
+    NoQrCode,
+}
+
+impl fmt::Display for QrDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrDecodeError::UnreadableImage(msg) => write!(f, "could not read the image: {}", msg),
+            QrDecodeError::NoQrCode => write!(f, "no QR code was found in the image"),
+        }
+    }
+}
+
+impl Serialize for QrDecodeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+pub fn decode_payload(payload: &str) -> DecodedQrCode {
+    let payment = if let Ok(epc) = epc_qr::parse_payload(payload) {
+        Payment::Epc(Box::new(epc))
+    } else if let Ok(bill) = swiss_qr::parse(payload) {
+        Payment::SwissQrBill(Box::new(bill))
+    } else {
+        Payment::Unknown
+    };
+    let iban = match &payment {
+        Payment::Epc(epc) => Some(&epc.iban),
+        Payment::SwissQrBill(bill) => Some(&bill.iban),
+        Payment::Unknown => None,
+    };
+    DecodedQrCode {
+        payload: payload.to_string(),
+        validation: iban.map(|iban| get_iban_vo_from_str(iban)),
+        payment,
+    }
+}
+
+// Decodes every readable QR code in a PNG or JPEG file
+pub fn decode_image(path: &Path) -> Result<Vec<DecodedQrCode>, QrDecodeError> {
+    let image = image::open(path)
+        .map_err(|e| QrDecodeError::UnreadableImage(e.to_string()))?
+        .to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32)[0],
+    );
+    // Grids that cannot be decoded are most likely false detections, so they are skipped
+    let decoded: Vec<DecodedQrCode> = prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| {
+            let mut content = Vec::new();
+            grid.decode_to(&mut content).ok()?;
+            Some(content)
+        })
+        .map(|content| decode_payload(&epc_qr::decode_payload_bytes(&content)))
+        .collect();
+    if decoded.is_empty() {
+        return Err(QrDecodeError::NoQrCode);
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use qrcode::QrCode;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_decode_payload() {
+        let epc = decode_payload("BCD\n002\n1\nSCT\n\nFranz Mustermann\nDE89370400440532013000\nEUR12.30");
+        assert!(matches!(epc.payment, Payment::Epc(_)));
+        assert!(epc.validation.unwrap().is_valid());
+
+        let broken_iban = decode_payload("BCD\n002\n1\nSCT\n\nFranz Mustermann\nDE89370400440532013001");
+        assert!(!broken_iban.validation.unwrap().is_valid());
+
+        let unknown = decode_payload("https://example.com");
+        assert_eq!(unknown.payment, Payment::Unknown);
+        assert_eq!(unknown.validation, None);
+    }
+
+    // A directory of the test in this process, so that concurrent test runs don't share files
+    fn test_directory(test: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("iban-qr-test-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_decode_image() {
+        let payload = "BCD\n002\n1\nSCT\n\nFranz Mustermann\nDE89370400440532013000\nEUR12.30";
+        let image = QrCode::new(payload)
+            .unwrap()
+            .render::<image::Luma<u8>>()
+            .build();
+        let directory = test_directory("image");
+        let path = directory.join("qr.png");
+        image.save(&path).unwrap();
+
+        let decoded = decode_image(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].payload, payload);
+        assert!(decoded[0].validation.as_ref().unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_decode_latin1_image() {
+        // A GiroCode in ISO-8859-1 has the bytes of that character set, not UTF-8
+        let payload = "BCD\n002\n2\nSCT\n\nFranz Mustermänn\nDE89370400440532013000\nEUR12.30";
+        let png = epc_qr::render(payload, epc_qr::ImageFormat::Png).unwrap();
+        let png = {
+            use base64::Engine;
+            base64::engine::general_purpose::STANDARD
+                .decode(png.trim_start_matches("data:image/png;base64,"))
+                .unwrap()
+        };
+        let directory = test_directory("latin1");
+        let path = directory.join("qr.png");
+        std::fs::write(&path, png).unwrap();
+
+        let decoded = decode_image(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded[0].payload, payload);
+        assert!(matches!(&decoded[0].payment, Payment::Epc(epc) if epc.name == "Franz Mustermänn"));
+    }
+
+    #[test]
+    fn test_decode_image_errors() {
+        let directory = test_directory("blank");
+        let path = directory.join("blank.png");
+        image::GrayImage::from_pixel(100, 100, image::Luma([255])).save(&path).unwrap();
+        let decoded = decode_image(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(decoded, Err(QrDecodeError::NoQrCode));
+
+        assert!(matches!(
+            decode_image(Path::new("does-not-exist.png")),
+            Err(QrDecodeError::UnreadableImage(_))
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+// Redaction of IBANs in text, CSV and JSON files. Every IBAN with a correct checksum is replaced
+// by a masked form of the same length, or by a token that is the same wherever the IBAN occurs.
+// Neither contains separators or quotes, so the structure of CSV and JSON files is kept.
+// The replacements are recorded in a mapping that can restore the original text, and the mapping
+// can be encrypted with a passphrase.
+use std::fmt;
+
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize, Serializer};
+use sha2::Sha256;
+
+use crate::text_scan;
+
+const TOKEN_PREFIX: &str = "IBANTOKEN";
+// The number of hexadecimal digits of the HMAC in a token
+const TOKEN_LENGTH: usize = 16;
+// The characters at the end that are left visible by masking
+const VISIBLE_END: usize = 4;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+#[derive(PartialEq, Debug)]
+pub enum RedactionError {
+    MissingKey,
+    InvalidMapping(String),
+    WrongPassphrase,
+}
+
+impl fmt::Display for RedactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedactionError::MissingKey => write!(f, "tokens need a secret key, without one the IBANs can be found from the tokens"),
+            RedactionError::InvalidMapping(msg) => write!(f, "the mapping file could not be read: {}", msg),
+            RedactionError::WrongPassphrase => write!(f, "the mapping file could not be decrypted with this passphrase"),
+        }
+    }
+}
+
+impl Serialize for RedactionError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RedactionMode<'a> {
+    // Everything but the country code and the last four characters is replaced by *
+    Mask,
+    // The IBAN is replaced by IBANTOKEN and an HMAC of the IBAN with the key
+    Token(&'a [u8]),
+}
+
+// A replacement, the offset is where it starts in the redacted text
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct MappingEntry {
+    pub offset: usize,
+    pub replacement: String,
+    pub original: String,
+}
+
+// The replacements in one redacted file
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct RedactedFile {
+    pub file: String,
+    pub entries: Vec<MappingEntry>,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Redaction {
+    pub text: String,
+    pub entries: Vec<MappingEntry>,
+}
+
+fn mask(original: &str) -> String {
+    let total = original.chars().filter(|c| c.is_ascii_alphanumeric()).count();
+    let mut seen = 0;
+    original
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen <= 2 || seen > total - VISIBLE_END {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+// Without a key anyone can compute the token of an IBAN, and find the IBAN by trying them
+pub fn token(iban: &str, key: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(iban.as_bytes());
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}{}", TOKEN_PREFIX, &digest[..TOKEN_LENGTH])
+}
+
+pub fn redact(text: &str, mode: RedactionMode<'_>) -> Result<Redaction, RedactionError> {
+    if mode == RedactionMode::Token(b"") {
+        return Err(RedactionError::MissingKey);
+    }
+    let mut redacted = String::with_capacity(text.len());
+    let mut entries = Vec::new();
+    let mut copied = 0;
+    for found in text_scan::find_ibans(text) {
+        if !found.validation.is_valid() {
+            continue;
+        }
+        let replacement = match mode {
+            RedactionMode::Mask => mask(&found.text),
+            RedactionMode::Token(key) => token(&found.validation.iban, key),
+        };
+        redacted.push_str(&text[copied..found.start]);
+        entries.push(MappingEntry {
+            offset: redacted.len(),
+            replacement: replacement.clone(),
+            original: found.text,
+        });
+        redacted.push_str(&replacement);
+        copied = found.end;
+    }
+    redacted.push_str(&text[copied..]);
+    Ok(Redaction { text: redacted, entries })
+}
+
+// Puts the original IBANs back, replacements that don't match the text are left alone
+pub fn restore(text: &str, entries: &[MappingEntry]) -> String {
+    let mut restored = text.to_string();
+    for entry in entries.iter().rev() {
+        let end = entry.offset + entry.replacement.len();
+        if restored.get(entry.offset..end) == Some(entry.replacement.as_str()) {
+            restored.replace_range(entry.offset..end, &entry.original);
+        }
+    }
+    restored
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+// The encrypted mapping is the salt, the nonce and the ChaCha20-Poly1305 ciphertext
+pub fn encrypt_mapping(mapping: &[u8], passphrase: &str) -> Vec<u8> {
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt));
+    let ciphertext = cipher
+        .encrypt(&nonce, mapping)
+        .expect("encryption only fails for inputs larger than 256 GiB");
+    [salt.as_slice(), nonce.as_slice(), &ciphertext].concat()
+}
+
+pub fn decrypt_mapping(encrypted: &[u8], passphrase: &str) -> Result<Vec<u8>, RedactionError> {
+    if encrypted.len() < SALT_LENGTH + NONCE_LENGTH {
+        return Err(RedactionError::InvalidMapping(String::from("the file is too short")));
+    }
+    let (salt, rest) = encrypted.split_at(SALT_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    ChaCha20Poly1305::new(&derive_key(passphrase, salt))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| RedactionError::WrongPassphrase)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const CSV: &str = "name,iban,note\n\
+                       Acme,DE89 3704 0044 0532 0130 00,paid\n\
+                       Globex,GB29NWBK60161331926819,\"typo DE89370400440532013001\"\n\
+                       Acme again,DE89370400440532013000,\n";
+
+    #[test]
+    fn test_mask() {
+        let redaction = redact(CSV, RedactionMode::Mask).unwrap();
+        assert_eq!(
+            redaction.text,
+            "name,iban,note\n\
+             Acme,DE** **** **** **** **30 00,paid\n\
+             Globex,GB****************6819,\"typo DE89370400440532013001\"\n\
+             Acme again,DE****************3000,\n"
+        );
+        assert_eq!(redaction.entries.len(), 3);
+        assert_eq!(restore(&redaction.text, &redaction.entries), CSV);
+    }
+
+    #[test]
+    fn test_tokens_are_stable() {
+        let redaction = redact(CSV, RedactionMode::Token(b"secret")).unwrap();
+        let tokens: Vec<&str> = redaction.entries.iter().map(|entry| entry.replacement.as_str()).collect();
+        assert_eq!(tokens[0], tokens[2]);
+        assert_ne!(tokens[0], tokens[1]);
+        assert_eq!(tokens[0], token("DE89370400440532013000", b"secret"));
+        assert_ne!(tokens[0], token("DE89370400440532013000", b"other key"));
+        assert_eq!(tokens[0].len(), TOKEN_PREFIX.len() + TOKEN_LENGTH);
+        assert!(!redaction.text.contains("DE89370400440532013000"));
+        assert_eq!(restore(&redaction.text, &redaction.entries), CSV);
+    }
+
+    #[test]
+    fn test_json_structure_is_kept() {
+        let json = r#"{"payee":{"iban":"GB29NWBK60161331926819"}}"#;
+        let redacted = redact(json, RedactionMode::Token(b"secret")).unwrap().text;
+        let value: serde_json::Value = serde_json::from_str(&redacted).unwrap();
+        assert!(value["payee"]["iban"].as_str().unwrap().starts_with(TOKEN_PREFIX));
+    }
+
+    #[test]
+    fn test_tokens_need_a_key() {
+        assert_eq!(redact(CSV, RedactionMode::Token(b"")), Err(RedactionError::MissingKey));
+    }
+
+    #[test]
+    fn test_encrypted_mapping() {
+        let encrypted = encrypt_mapping(b"[]", "correct horse");
+        assert_eq!(decrypt_mapping(&encrypted, "correct horse"), Ok(b"[]".to_vec()));
+        assert_eq!(decrypt_mapping(&encrypted, "wrong horse"), Err(RedactionError::WrongPassphrase));
+        assert!(matches!(decrypt_mapping(b"short", "correct horse"), Err(RedactionError::InvalidMapping(_))));
+    }
+}
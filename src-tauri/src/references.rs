@@ -0,0 +1,455 @@
+// National structured payment references and their check digits. The scheme of a reference
+// usually follows from the country of the beneficiary IBAN, since most of them are plain digits.
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Deserialize;
+
+use crate::rf_reference::RfReference;
+use crate::swiss_qr::{is_qr_iban, mod10_recursive_check_digit};
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ReferenceScheme {
+    // ISO 11649, accepted in every SEPA country
+    #[serde(rename = "RF")]
+    Rf,
+    // Belgian "gestructureerde mededeling" / "communication structurée", mod 97
+    #[serde(rename = "OGM")]
+    BelgianOgm,
+    // Norwegian "kundeidentifikasjon", mod 10 or mod 11
+    #[serde(rename = "KID")]
+    NorwegianKid,
+    // Finnish "viitenumero", weights 7-3-1
+    #[serde(rename = "FI")]
+    FinnishReference,
+    // Swiss ESR and QR reference, mod 10 recursive
+    #[serde(rename = "QRR")]
+    SwissQrr,
+    // Slovenian reference model 12, mod 11
+    #[serde(rename = "SI12")]
+    SloveneSi12,
+}
+
+impl ReferenceScheme {
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceScheme::Rf => "RF",
+            ReferenceScheme::BelgianOgm => "OGM",
+            ReferenceScheme::NorwegianKid => "KID",
+            ReferenceScheme::FinnishReference => "FI",
+            ReferenceScheme::SwissQrr => "QRR",
+            ReferenceScheme::SloveneSi12 => "SI12",
+        }
+    }
+
+    // The IBAN countries the scheme can be used with, `None` if it is not tied to a country
+    pub fn countries(self) -> Option<&'static [&'static str]> {
+        match self {
+            ReferenceScheme::Rf => None,
+            ReferenceScheme::BelgianOgm => Some(&["BE"]),
+            ReferenceScheme::NorwegianKid => Some(&["NO"]),
+            ReferenceScheme::FinnishReference => Some(&["FI"]),
+            ReferenceScheme::SwissQrr => Some(&["CH", "LI"]),
+            ReferenceScheme::SloveneSi12 => Some(&["SI"]),
+        }
+    }
+
+    fn for_country(country_code: &str) -> Option<ReferenceScheme> {
+        match country_code {
+            "BE" => Some(ReferenceScheme::BelgianOgm),
+            "NO" => Some(ReferenceScheme::NorwegianKid),
+            "FI" => Some(ReferenceScheme::FinnishReference),
+            "CH" | "LI" => Some(ReferenceScheme::SwissQrr),
+            "SI" => Some(ReferenceScheme::SloveneSi12),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for ReferenceScheme {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ReferenceError {
+    UnknownScheme,
+    InvalidCharacters,
+    InvalidLength { min: usize, max: usize },
+    InvalidCheckDigits,
+    WrongCountry { scheme: ReferenceScheme, country: String },
+    QrReferenceRequiresQrIban,
+}
+
+impl fmt::Display for ReferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceError::UnknownScheme => write!(f, "the reference scheme could not be recognized"),
+            ReferenceError::InvalidCharacters => write!(f, "the reference contains invalid characters"),
+            ReferenceError::InvalidLength { min, max } => {
+                write!(f, "the reference must have {} to {} digits", min, max)
+            }
+            ReferenceError::InvalidCheckDigits => write!(f, "the check digits are incorrect"),
+            ReferenceError::WrongCountry { scheme, country } => write!(
+                f,
+                "{} references cannot be used with an IBAN from {}",
+                scheme.name(),
+                country
+            ),
+            ReferenceError::QrReferenceRequiresQrIban => {
+                write!(f, "QR references can only be used with a QR-IBAN")
+            }
+        }
+    }
+}
+
+impl Serialize for ReferenceError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+fn digits_of(s: &str) -> Option<Vec<u32>> {
+    s.chars().map(|c| c.to_digit(10)).collect()
+}
+
+fn check_digits_only(digits: &str, min: usize, max: usize) -> Result<(), ReferenceError> {
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ReferenceError::InvalidCharacters);
+    }
+    if digits.len() < min || digits.len() > max {
+        return Err(ReferenceError::InvalidLength { min, max });
+    }
+    Ok(())
+}
+
+// OGM: the last two digits are the first ten modulo 97, where 0 becomes 97
+fn ogm_check_digits(base: &str) -> Option<String> {
+    let remainder = base.parse::<u64>().ok()? % 97;
+    Some(format!("{:02}", if remainder == 0 { 97 } else { remainder }))
+}
+
+// KID modulus 10 is the Luhn algorithm
+fn luhn_check_digit(base: &str) -> Option<String> {
+    let sum: u32 = digits_of(base)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 0 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    Some(((10 - sum % 10) % 10).to_string())
+}
+
+// KID modulus 11 uses the weights 2 to 7 from the right. A remainder of 1 would need the check
+// digit "-", which banks no longer issue, so such bases have no valid check digit.
+fn kid_mod11_check_digit(base: &str) -> Option<String> {
+    let sum: u32 = digits_of(base)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| d * (i as u32 % 6 + 2))
+        .sum();
+    match 11 - sum % 11 {
+        11 => Some(String::from("0")),
+        10 => None,
+        check => Some(check.to_string()),
+    }
+}
+
+// Viitenumero: the weights 7, 3, 1 are repeated from the right
+fn finnish_check_digit(base: &str) -> Option<String> {
+    const WEIGHTS: [u32; 3] = [7, 3, 1];
+    let sum: u32 = digits_of(base)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| d * WEIGHTS[i % 3])
+        .sum();
+    Some(((10 - sum % 10) % 10).to_string())
+}
+
+// SI12: weights 2, 3, 4, ... from the right, check digits 10 and 11 become 0
+fn si12_check_digit(base: &str) -> Option<String> {
+    let sum: u32 = digits_of(base)?
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| d * (i as u32 + 2))
+        .sum();
+    let check = 11 - sum % 11;
+    Some(if check >= 10 { 0 } else { check }.to_string())
+}
+
+// Strips the presentation of a reference down to the characters the check digits are computed over
+fn normalize(reference: &str) -> String {
+    reference
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '+' | '*' | '/' | '-'))
+        .collect::<String>()
+        .to_uppercase()
+}
+
+pub fn detect_scheme(reference: &str, country_code: Option<&str>) -> Option<ReferenceScheme> {
+    let trimmed = reference.trim();
+    let upper = trimmed.to_uppercase();
+    if upper.starts_with("RF") {
+        Some(ReferenceScheme::Rf)
+    } else if upper.starts_with("SI12") {
+        Some(ReferenceScheme::SloveneSi12)
+    } else if (trimmed.starts_with("+++") && trimmed.ends_with("+++"))
+        || (trimmed.starts_with("***") && trimmed.ends_with("***"))
+    {
+        Some(ReferenceScheme::BelgianOgm)
+    } else {
+        country_code.and_then(ReferenceScheme::for_country)
+    }
+}
+
+pub fn validate(reference: &str, scheme: ReferenceScheme) -> Result<(), ReferenceError> {
+    let normalized = normalize(reference);
+    let split = |len: usize| normalized.split_at(normalized.len().saturating_sub(len));
+    let check = |base: &str, check: &str, expected: Option<String>| {
+        if expected.as_deref() == Some(check) && !base.is_empty() {
+            Ok(())
+        } else {
+            Err(ReferenceError::InvalidCheckDigits)
+        }
+    };
+    match scheme {
+        ReferenceScheme::Rf => RfReference::parse(reference)
+            .map(|_| ())
+            .map_err(|_| ReferenceError::InvalidCheckDigits),
+        ReferenceScheme::BelgianOgm => {
+            check_digits_only(&normalized, 12, 12)?;
+            let (base, digits) = split(2);
+            check(base, digits, ogm_check_digits(base))
+        }
+        ReferenceScheme::NorwegianKid => {
+            check_digits_only(&normalized, 2, 25)?;
+            let (base, digit) = split(1);
+            // The bank agreement decides between modulus 10 and 11, either is accepted here
+            check(base, digit, luhn_check_digit(base))
+                .or_else(|_| check(base, digit, kid_mod11_check_digit(base)))
+        }
+        ReferenceScheme::FinnishReference => {
+            check_digits_only(&normalized, 4, 20)?;
+            let (base, digit) = split(1);
+            check(base, digit, finnish_check_digit(base))
+        }
+        ReferenceScheme::SwissQrr => {
+            // 27 digits for QR and ESR references, 16 for the short ESR references
+            check_digits_only(&normalized, 16, 27)?;
+            if normalized.len() != 16 && normalized.len() != 27 {
+                return Err(ReferenceError::InvalidLength { min: 16, max: 27 });
+            }
+            let (base, digit) = split(1);
+            check(base, digit, mod10_recursive_check_digit(base).map(|d| d.to_string()))
+        }
+        ReferenceScheme::SloveneSi12 => {
+            let digits = normalized.strip_prefix("SI12").ok_or(ReferenceError::InvalidCharacters)?;
+            check_digits_only(digits, 2, 20)?;
+            let (base, digit) = digits.split_at(digits.len() - 1);
+            check(base, digit, si12_check_digit(base))
+        }
+    }
+}
+
+// Checks that the scheme may be used for a payment to the given IBAN
+pub fn validate_for_iban(scheme: ReferenceScheme, iban: &str) -> Result<(), ReferenceError> {
+    let country = iban.get(0..2).unwrap_or("").to_uppercase();
+    if let Some(countries) = scheme.countries() {
+        if !countries.contains(&country.as_str()) {
+            return Err(ReferenceError::WrongCountry { scheme, country });
+        }
+    }
+    if scheme == ReferenceScheme::SwissQrr && !is_qr_iban(iban) {
+        return Err(ReferenceError::QrReferenceRequiresQrIban);
+    }
+    Ok(())
+}
+
+// Appends the check digits to a base reference and renders it the way it is printed on invoices
+pub fn generate(scheme: ReferenceScheme, base: &str) -> Result<String, ReferenceError> {
+    let base = normalize(base);
+    match scheme {
+        ReferenceScheme::Rf => RfReference::generate(&base)
+            .map(|reference| reference.to_string())
+            .map_err(|_| ReferenceError::InvalidCharacters),
+        ReferenceScheme::BelgianOgm => {
+            check_digits_only(&base, 10, 10)?;
+            let full = base.clone() + &ogm_check_digits(&base).unwrap_or_default();
+            Ok(format!("+++{}/{}/{}+++", &full[0..3], &full[3..7], &full[7..12]))
+        }
+        ReferenceScheme::NorwegianKid => {
+            check_digits_only(&base, 1, 24)?;
+            luhn_check_digit(&base)
+                .map(|check| base.clone() + &check)
+                .ok_or(ReferenceError::InvalidCharacters)
+        }
+        ReferenceScheme::FinnishReference => {
+            check_digits_only(&base, 3, 19)?;
+            Ok(base.clone() + &finnish_check_digit(&base).unwrap_or_default())
+        }
+        ReferenceScheme::SwissQrr => {
+            check_digits_only(&base, 1, 26)?;
+            // QR references are always padded to 27 digits
+            let padded = format!("{:0>26}", base);
+            Ok(padded.clone() + &mod10_recursive_check_digit(&padded).unwrap_or(0).to_string())
+        }
+        ReferenceScheme::SloveneSi12 => {
+            let digits = base.strip_prefix("SI12").unwrap_or(&base);
+            check_digits_only(digits, 1, 19)?;
+            Ok(format!("SI12 {}{}", digits, si12_check_digit(digits).unwrap_or_default()))
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct ReferenceVO {
+    pub reference: String,
+    pub scheme: Option<ReferenceScheme>,
+    pub error: Option<ReferenceError>,
+}
+
+impl Serialize for ReferenceVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ReferenceVO", 4)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.serialize_field("scheme", &self.scheme)?;
+        state.serialize_field("isValid", &self.error.is_none())?;
+        state.serialize_field("error", &self.error)?;
+        state.end()
+    }
+}
+
+// Detects and validates a reference, and when the beneficiary IBAN is known also checks the pairing
+pub fn get_reference_vo(reference: &str, iban: Option<&str>) -> ReferenceVO {
+    let country_code = iban.and_then(|iban| iban.get(0..2)).map(str::to_uppercase);
+    let scheme = detect_scheme(reference, country_code.as_deref());
+    let error = match scheme {
+        Some(scheme) => validate(reference, scheme)
+            .and_then(|_| iban.map_or(Ok(()), |iban| validate_for_iban(scheme, iban)))
+            .err(),
+        None => Some(ReferenceError::UnknownScheme),
+    };
+    ReferenceVO {
+        reference: String::from(reference),
+        scheme,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_detect_scheme() {
+        assert_eq!(detect_scheme("RF18539007547034", None), Some(ReferenceScheme::Rf));
+        assert_eq!(detect_scheme("+++090/9337/55493+++", None), Some(ReferenceScheme::BelgianOgm));
+        assert_eq!(detect_scheme("SI12 1234567890", Some("DE")), Some(ReferenceScheme::SloveneSi12));
+        assert_eq!(detect_scheme("1234561", Some("NO")), Some(ReferenceScheme::NorwegianKid));
+        assert_eq!(detect_scheme("1234561", None), None);
+    }
+
+    #[test]
+    fn test_belgian_ogm() {
+        assert_eq!(validate("+++090/9337/55493+++", ReferenceScheme::BelgianOgm), Ok(()));
+        assert_eq!(validate("***090/9337/55493***", ReferenceScheme::BelgianOgm), Ok(()));
+        assert_eq!(
+            validate("+++090/9337/55494+++", ReferenceScheme::BelgianOgm),
+            Err(ReferenceError::InvalidCheckDigits)
+        );
+        assert_eq!(
+            generate(ReferenceScheme::BelgianOgm, "0909337554").unwrap(),
+            "+++090/9337/55493+++"
+        );
+        // A remainder of 0 is written as 97
+        assert_eq!(generate(ReferenceScheme::BelgianOgm, "0000000097").unwrap(), "+++000/0000/09797+++");
+    }
+
+    #[test]
+    fn test_norwegian_kid() {
+        assert_eq!(validate("1234567897", ReferenceScheme::NorwegianKid), Ok(()));
+        assert_eq!(validate("12345678903", ReferenceScheme::NorwegianKid), Ok(()));
+        assert_eq!(
+            validate("1234567891", ReferenceScheme::NorwegianKid),
+            Err(ReferenceError::InvalidCheckDigits)
+        );
+        assert_eq!(generate(ReferenceScheme::NorwegianKid, "123456789").unwrap(), "1234567897");
+    }
+
+    #[test]
+    fn test_finnish_reference() {
+        assert_eq!(validate("1232", ReferenceScheme::FinnishReference), Ok(()));
+        assert_eq!(validate("12345 67891 0", ReferenceScheme::FinnishReference), Ok(()));
+        assert_eq!(
+            validate("1233", ReferenceScheme::FinnishReference),
+            Err(ReferenceError::InvalidCheckDigits)
+        );
+        assert_eq!(generate(ReferenceScheme::FinnishReference, "123").unwrap(), "1232");
+        assert_eq!(
+            validate("12", ReferenceScheme::FinnishReference),
+            Err(ReferenceError::InvalidLength { min: 4, max: 20 })
+        );
+    }
+
+    #[test]
+    fn test_swiss_qrr() {
+        assert_eq!(validate("21 00000 00003 13947 14300 09017", ReferenceScheme::SwissQrr), Ok(()));
+        assert_eq!(
+            generate(ReferenceScheme::SwissQrr, "21000000000313947143000901").unwrap(),
+            "210000000003139471430009017"
+        );
+        assert_eq!(
+            validate("2100000000031394714300090", ReferenceScheme::SwissQrr),
+            Err(ReferenceError::InvalidLength { min: 16, max: 27 })
+        );
+    }
+
+    #[test]
+    fn test_slovene_si12() {
+        assert_eq!(generate(ReferenceScheme::SloveneSi12, "12345").unwrap(), "SI12 123455");
+        assert_eq!(generate(ReferenceScheme::SloveneSi12, "123456").unwrap(), "SI12 1234560");
+        assert_eq!(validate("SI12 123455", ReferenceScheme::SloveneSi12), Ok(()));
+        assert_eq!(
+            validate("SI12 123456", ReferenceScheme::SloveneSi12),
+            Err(ReferenceError::InvalidCheckDigits)
+        );
+    }
+
+    #[test]
+    fn test_get_reference_vo() {
+        let ogm = get_reference_vo("+++090/9337/55493+++", Some("BE68539007547034"));
+        assert_eq!(ogm.scheme, Some(ReferenceScheme::BelgianOgm));
+        assert_eq!(ogm.error, None);
+
+        let wrong_country = get_reference_vo("+++090/9337/55493+++", Some("DE89370400440532013000"));
+        assert_eq!(
+            wrong_country.error,
+            Some(ReferenceError::WrongCountry {
+                scheme: ReferenceScheme::BelgianOgm,
+                country: String::from("DE")
+            })
+        );
+
+        let qrr = get_reference_vo("210000000003139471430009017", Some("CH9300762011623852957"));
+        assert_eq!(qrr.error, Some(ReferenceError::QrReferenceRequiresQrIban));
+
+        let rf = get_reference_vo("RF18539007547034", Some("NO9386011117947"));
+        assert_eq!(rf.error, None);
+
+        assert_eq!(get_reference_vo("1234", None).error, Some(ReferenceError::UnknownScheme));
+    }
+}
@@ -0,0 +1,219 @@
+// ISO 11649 structured creditor references ("RF references"). They use the same MOD 97-10
+// check as IBANs: RF + two check digits + a base reference of up to 21 alphanumeric characters.
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::iso7064;
+
+const MAX_BASE_LENGTH: usize = 21;
+
+#[derive(PartialEq, Debug)]
+pub enum RfReferenceError {
+    MissingPrefix,
+    InvalidCheckDigits,
+    InvalidLength(usize),
+    NotAlphanumeric,
+    InvalidChecksum,
+}
+
+impl fmt::Display for RfReferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RfReferenceError::MissingPrefix => write!(f, "the reference does not start with RF"),
+            RfReferenceError::InvalidCheckDigits => write!(f, "the check digits are not numeric"),
+            RfReferenceError::InvalidLength(len) => write!(
+                f,
+                "the base reference has {} characters, it must have 1 to {}",
+                len, MAX_BASE_LENGTH
+            ),
+            RfReferenceError::NotAlphanumeric => {
+                write!(f, "the reference can only contain the characters 0-9 and A-Z")
+            }
+            RfReferenceError::InvalidChecksum => write!(f, "the check digits are incorrect"),
+        }
+    }
+}
+
+impl Serialize for RfReferenceError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RfReference {
+    // The reference without whitespace, e.g. "RF18539007547034"
+    electronic: String,
+}
+
+fn normalize(reference: &str) -> String {
+    reference
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn check_base(base: &str) -> Result<(), RfReferenceError> {
+    let length = base.chars().count();
+    if length == 0 || length > MAX_BASE_LENGTH {
+        return Err(RfReferenceError::InvalidLength(length));
+    }
+    if !base.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(RfReferenceError::NotAlphanumeric);
+    }
+    Ok(())
+}
+
+// The same rearrangement as for IBANs: the prefix and check digits are moved to the end
+fn rearrange(base: &str, check_digits: &str) -> String {
+    format!("{}RF{}", base, check_digits)
+}
+
+impl RfReference {
+    pub fn parse(reference: &str) -> Result<RfReference, RfReferenceError> {
+        let electronic = normalize(reference);
+        if !electronic.starts_with("RF") {
+            return Err(RfReferenceError::MissingPrefix);
+        }
+        let check_digits = electronic.get(2..4).unwrap_or("");
+        if check_digits.len() != 2 || !check_digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(RfReferenceError::InvalidCheckDigits);
+        }
+        let base = &electronic[4..];
+        check_base(base)?;
+        if !iso7064::System::Mod97_10.verify(&rearrange(base, check_digits)) {
+            return Err(RfReferenceError::InvalidChecksum);
+        }
+        Ok(RfReference { electronic })
+    }
+
+    // Creates an RF reference from a creditor's own reference, e.g. an invoice number
+    pub fn generate(base: &str) -> Result<RfReference, RfReferenceError> {
+        let base = normalize(base);
+        check_base(&base)?;
+        let check_digits = iso7064::System::Mod97_10
+            .compute(&rearrange(&base, ""))
+            .map_err(|_| RfReferenceError::NotAlphanumeric)?;
+        Ok(RfReference {
+            electronic: format!("RF{}{}", check_digits, base),
+        })
+    }
+
+    pub fn electronic_str(&self) -> &str {
+        &self.electronic
+    }
+}
+
+// The paper format, in groups of four characters
+impl fmt::Display for RfReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<&str> = self
+            .electronic
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or(""))
+            .collect();
+        write!(f, "{}", groups.join(" "))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct RfReferenceVO {
+    pub reference: String,
+    pub formatted: Option<String>,
+    pub error: Option<RfReferenceError>,
+}
+
+impl RfReferenceVO {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl Serialize for RfReferenceVO {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("RfReferenceVO", 4)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.serialize_field("isValid", &self.is_valid())?;
+        state.serialize_field("formatted", &self.formatted)?;
+        state.serialize_field("error", &self.error)?;
+        state.end()
+    }
+}
+
+pub fn get_rf_reference_vo_from_str(reference: &str) -> RfReferenceVO {
+    match RfReference::parse(reference) {
+        Ok(parsed) => RfReferenceVO {
+            reference: String::from(reference),
+            formatted: Some(parsed.to_string()),
+            error: None,
+        },
+        Err(error) => RfReferenceVO {
+            reference: String::from(reference),
+            formatted: None,
+            error: Some(error),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let reference = RfReference::parse("RF18 5390 0754 7034").unwrap();
+        assert_eq!(reference.electronic_str(), "RF18539007547034");
+        assert_eq!(reference.to_string(), "RF18 5390 0754 7034");
+
+        assert!(RfReference::parse("rf18000000000539007547034").is_ok());
+        assert!(RfReference::parse("RF712348231").is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(RfReference::parse("DE18539007547034"), Err(RfReferenceError::MissingPrefix));
+        assert_eq!(RfReference::parse("RFAB539007547034"), Err(RfReferenceError::InvalidCheckDigits));
+        assert_eq!(RfReference::parse("RF18"), Err(RfReferenceError::InvalidLength(0)));
+        assert_eq!(
+            RfReference::parse("RF181234567890123456789012"),
+            Err(RfReferenceError::InvalidLength(22))
+        );
+        assert_eq!(RfReference::parse("RF18539007-547034"), Err(RfReferenceError::NotAlphanumeric));
+        assert_eq!(RfReference::parse("RF19539007547034"), Err(RfReferenceError::InvalidChecksum));
+    }
+
+    #[test]
+    fn test_generate() {
+        assert_eq!(RfReference::generate("539007547034").unwrap().electronic_str(), "RF18539007547034");
+        assert_eq!(RfReference::generate("2348231").unwrap().electronic_str(), "RF712348231");
+        let generated = RfReference::generate("invoice 2023 0042").unwrap();
+        assert_eq!(RfReference::parse(generated.electronic_str()), Ok(generated));
+        assert_eq!(RfReference::generate(""), Err(RfReferenceError::InvalidLength(0)));
+    }
+
+    #[test]
+    fn test_rf_reference_vo() {
+        assert_eq!(
+            get_rf_reference_vo_from_str("RF18539007547034"),
+            RfReferenceVO {
+                reference: String::from("RF18539007547034"),
+                formatted: Some(String::from("RF18 5390 0754 7034")),
+                error: None,
+            }
+        );
+        assert_eq!(
+            get_rf_reference_vo_from_str("RF19539007547034").error,
+            Some(RfReferenceError::InvalidChecksum)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+// Generated valid IBANs for the property tests of code that takes IBANs. With the proptest
+// feature there are proptest strategies, and with the arbitrary feature `ArbitraryIban` can be
+// used in fuzz targets. Nothing in the app itself uses them, other crates get them from the
+// iban_validator_lib library with one of the features, like the fuzz targets in /fuzz.
+
+#[cfg(any(test, feature = "proptest"))]
+use proptest::prelude::*;
+
+use crate::country_data;
+use crate::generate::{self, GenerateOptions};
+
+// The countries with IBANs that can be generated, in alphabetical order
+pub fn countries() -> Vec<&'static str> {
+    let mut countries: Vec<&'static str> = country_data::get_country_codes_and_length()
+        .into_keys()
+        .filter(|country_code| country_data::get_bban_structure(country_code).is_some())
+        .collect();
+    countries.sort_unstable();
+    countries
+}
+
+fn generate_iban(country_code: &str, seed: u64, national_check_digits: bool) -> String {
+    let options = GenerateOptions {
+        country_code: String::from(country_code),
+        bank_code: None,
+        national_check_digits,
+        seed: Some(seed),
+    };
+    generate::generate_ibans(&options, 1)
+        .map(|mut ibans| ibans.remove(0))
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Valid IBANs of every country, with and without correct national check digits
+#[cfg(any(test, feature = "proptest"))]
+pub fn iban() -> impl Strategy<Value = String> {
+    (proptest::sample::select(countries()), any::<u64>(), any::<bool>())
+        .prop_map(|(country_code, seed, national_check_digits)| generate_iban(country_code, seed, national_check_digits))
+}
+
+// Valid IBANs of one country with correct national check digits, panics for unknown countries
+#[cfg(any(test, feature = "proptest"))]
+pub fn iban_of_country(country_code: &str) -> impl Strategy<Value = String> {
+    let country_code = String::from(country_code);
+    any::<u64>().prop_map(move |seed| generate_iban(&country_code, seed, true))
+}
+
+#[cfg(feature = "arbitrary")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArbitraryIban(pub String);
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ArbitraryIban {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let country_code = *u.choose(&countries())?;
+        Ok(ArbitraryIban(generate_iban(country_code, u.arbitrary()?, u.arbitrary()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{get_iban_vo_from_str, national_check};
+
+    proptest! {
+        #[test]
+        fn generated_ibans_are_valid(iban in iban()) {
+            prop_assert!(get_iban_vo_from_str(&iban).is_valid(), "{}", iban);
+        }
+
+        #[test]
+        fn generated_ibans_have_national_check_digits(iban in iban_of_country("FR")) {
+            prop_assert_eq!(national_check::tests::is_valid_national("FR", &iban[4..]), Some(true));
+        }
+    }
+}
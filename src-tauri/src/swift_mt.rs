@@ -0,0 +1,404 @@
+// SWIFT MT messages from legacy bank channels: MT940 statements and MT103 customer transfers. A file
+// has either messages with SWIFT blocks, {1:...}{2:I103...}{4:...-}, or only the text of the
+// messages, separated by lines with a single dash. The accounts in the account identification of
+// a statement (:25:), the beneficiary (:59:, :59A:, :59F:) and the account with institution
+// (:57A:) are classified as IBANs, which are validated, or domestic account numbers, which are
+// checked where the app can: German bank code/account number when the Bundesbank file is loaded,
+// and US routing numbers given as //FW clearing codes.
+use std::fmt;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::domestic_account::get_domestic_account_vo_from_str;
+use crate::epc_qr::is_valid_bic;
+use crate::{get_iban_vo_from_str, BankFiles};
+
+#[derive(PartialEq, Debug)]
+pub enum SwiftMtError {
+    NoMessages,
+}
+
+impl fmt::Display for SwiftMtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwiftMtError::NoMessages => write!(f, "the file has no MT940 or MT103 messages"),
+        }
+    }
+}
+
+impl Serialize for SwiftMtError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AccountKind {
+    Iban,
+    Domestic,
+    Bic,
+}
+
+impl Serialize for AccountKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            AccountKind::Iban => "iban",
+            AccountKind::Domestic => "domestic",
+            AccountKind::Bic => "bic",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct AccountField {
+    // The tag of the field, e.g. 57A
+    pub tag: String,
+    pub value: String,
+    pub kind: AccountKind,
+    // `None` for domestic account numbers that can't be checked
+    pub is_valid: Option<bool>,
+}
+
+impl Serialize for AccountField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("AccountField", 4)?;
+        state.serialize_field("tag", &self.tag)?;
+        state.serialize_field("value", &self.value)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("isValid", &self.is_valid)?;
+        state.end()
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct MtMessage {
+    // The position of the message in the file, counting from 1
+    pub index: usize,
+    // 940 or 103, from the application header or else from the fields of the message
+    pub message_type: Option<String>,
+    // The transaction reference number (:20:)
+    pub reference: Option<String>,
+    pub accounts: Vec<AccountField>,
+}
+
+impl MtMessage {
+    pub fn is_valid(&self) -> bool {
+        self.accounts.iter().all(|account| account.is_valid != Some(false))
+    }
+}
+
+impl Serialize for MtMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("MtMessage", 5)?;
+        state.serialize_field("index", &self.index)?;
+        state.serialize_field("messageType", &self.message_type)?;
+        state.serialize_field("reference", &self.reference)?;
+        state.serialize_field("accounts", &self.accounts)?;
+        state.serialize_field("isValid", &self.is_valid())?;
+        state.end()
+    }
+}
+
+// The message type and the text block of every message
+fn split_messages(text: &str) -> Vec<(Option<String>, String)> {
+    if !text.contains("{4:") {
+        let mut messages = vec![String::new()];
+        for line in text.lines() {
+            if line.trim() == "-" {
+                messages.push(String::new());
+            } else if let Some(message) = messages.last_mut() {
+                message.push_str(line);
+                message.push('\n');
+            }
+        }
+        return messages.into_iter().map(|message| (None, message)).collect();
+    }
+    let mut messages = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{4:") {
+        // The application header is I for input or O for output, followed by the message type
+        let message_type = rest[..start]
+            .rfind("{2:")
+            .and_then(|header| rest.get(header + 4..header + 7))
+            .filter(|message_type| message_type.chars().all(|c| c.is_ascii_digit()))
+            .map(String::from);
+        let body = &rest[start + 3..];
+        let end = body.find("-}").unwrap_or(body.len());
+        messages.push((message_type, body[..end].to_string()));
+        rest = &body[end..];
+    }
+    messages
+}
+
+// The fields of a text block as (tag, value), with the continuation lines in the value
+fn fields(block: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in block.lines() {
+        let line = line.trim_end();
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| {
+                let bytes = tag.as_bytes();
+                (2..=3).contains(&bytes.len())
+                    && bytes[..2].iter().all(u8::is_ascii_digit)
+                    && bytes[2..].iter().all(u8::is_ascii_uppercase)
+            });
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag.to_string(), value.to_string())),
+            (None, Some((_, value))) if !line.is_empty() => {
+                value.push('\n');
+                value.push_str(line);
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn looks_like_iban(account: &str) -> bool {
+    let bytes = account.as_bytes();
+    bytes.len() >= 15 && bytes[..2].iter().all(u8::is_ascii_uppercase) && bytes[2..4].iter().all(u8::is_ascii_digit)
+}
+
+// The account of a statement can be followed by the ISO 4217 code of its currency, an IBAN that is
+// valid with the three letters keeps them
+fn without_currency(account: String) -> String {
+    let split = account.len().saturating_sub(3);
+    let has_currency = split > 0 && account.as_bytes()[split..].iter().all(u8::is_ascii_uppercase);
+    if has_currency && !get_iban_vo_from_str(&account).is_valid() {
+        account[..split].to_string()
+    } else {
+        account
+    }
+}
+
+fn account_field(tag: &str, account: &str, bank_files: &BankFiles) -> AccountField {
+    let account: String = account.split_whitespace().collect::<String>().to_uppercase();
+    let account = if tag == "25" { without_currency(account) } else { account };
+    let (kind, is_valid) = if looks_like_iban(&account) {
+        (AccountKind::Iban, Some(get_iban_vo_from_str(&account).is_valid()))
+    } else if let Some(routing_number) = account.strip_prefix("//FW") {
+        (AccountKind::Domestic, Some(get_domestic_account_vo_from_str("US", routing_number).is_valid()))
+    } else {
+        // German statements identify the account as bank code/account number
+        let is_valid = account
+            .split_once('/')
+            .filter(|(blz, account_number)| blz.len() == 8 && !account_number.is_empty())
+            .and_then(|(blz, account_number)| {
+                bank_files.bank_directory.as_ref()?.check_account(blz, account_number).is_valid
+            });
+        (AccountKind::Domestic, is_valid)
+    };
+    AccountField {
+        tag: tag.to_string(),
+        value: account,
+        kind,
+        is_valid,
+    }
+}
+
+fn bic_field(tag: &str, bic: &str) -> AccountField {
+    let bic = bic.trim().to_uppercase();
+    AccountField {
+        tag: tag.to_string(),
+        is_valid: Some(is_valid_bic(&bic)),
+        value: bic,
+        kind: AccountKind::Bic,
+    }
+}
+
+// The accounts of a field, a party field starts with /account on its own line
+fn accounts(tag: &str, value: &str, bank_files: &BankFiles) -> Vec<AccountField> {
+    let mut lines = value.lines().map(str::trim).filter(|line| !line.is_empty());
+    match tag {
+        "25" => lines.next().map(|account| account_field(tag, account, bank_files)).into_iter().collect(),
+        "59" | "59A" | "59F" | "57A" => {
+            let mut accounts = Vec::new();
+            let mut line = lines.next();
+            if let Some(account) = line.filter(|line| line.starts_with('/')) {
+                // Clearing codes like //FW keep their slashes
+                let account = if account.starts_with("//") { account } else { &account[1..] };
+                accounts.push(account_field(tag, account, bank_files));
+                line = lines.next();
+            }
+            if let (Some(bic), "59A" | "57A") = (line, tag) {
+                accounts.push(bic_field(tag, bic));
+            }
+            accounts
+        }
+        _ => Vec::new(),
+    }
+}
+
+// German bank code/account numbers are checked with the bank directory of the `BankFiles`
+pub fn scan_messages(text: &str, bank_files: &BankFiles) -> Result<Vec<MtMessage>, SwiftMtError> {
+    let messages: Vec<MtMessage> = split_messages(text)
+        .into_iter()
+        .map(|(message_type, block)| (message_type, fields(&block)))
+        .filter(|(_, fields)| !fields.is_empty())
+        .enumerate()
+        .map(|(i, (message_type, fields))| {
+            let has = |tags: &[&str]| fields.iter().any(|(tag, _)| tags.contains(&tag.as_str()));
+            let message_type = message_type.or_else(|| {
+                if has(&["60F", "60M", "61", "62F"]) {
+                    Some(String::from("940"))
+                } else if has(&["23B", "32A"]) {
+                    Some(String::from("103"))
+                } else {
+                    None
+                }
+            });
+            MtMessage {
+                index: i + 1,
+                message_type,
+                reference: fields.iter().find(|(tag, _)| tag == "20").map(|(_, value)| value.trim().to_string()),
+                accounts: fields.iter().flat_map(|(tag, value)| accounts(tag, value, bank_files)).collect(),
+            }
+        })
+        .collect();
+    if messages.is_empty() {
+        return Err(SwiftMtError::NoMessages);
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bundesbank;
+
+    const MT103: &str = "{1:F01COBADEFFAXXX0000000000}{2:I103BNPAFRPPXXXXN}{4:
+:20:PAYMENT-1
+:23B:CRED
+:32A:240131EUR1250,00
+:50K:/DE89370400440532013000
+ACME GMBH
+:57A:BNPAFRPP
+:59:/FR1420041010050500013M02606
+INITECH SARL
+PARIS
+:71A:SHA
+-}{1:F01COBADEFFAXXX0000000000}{2:I103CHASUS33XXXXN}{4:
+:20:PAYMENT-2
+:23B:CRED
+:32A:240131USD100,00
+:57A://FW021000022
+CHASUS33
+:59:/123456789
+GLOBEX CORP
+-}";
+
+    const MT940: &str = ":20:STATEMENT-1
+:25:DE89370400440532013000
+:28C:1/1
+:60F:C240130EUR1000,00
+:61:2401310131D12,50NTRFNONREF
+:86:Invoice 1
+:62F:C240131EUR987,50
+-
+:20:STATEMENT-2
+:25:37040044/532013000
+:60F:C240130EUR0,00
+:62F:C240131EUR0,00
+-
+";
+
+    #[test]
+    fn test_mt103() {
+        let messages = scan_messages(MT103, &BankFiles::default()).unwrap();
+        assert_eq!(messages.len(), 2);
+        let first = &messages[0];
+        assert_eq!((first.message_type.as_deref(), first.reference.as_deref()), (Some("103"), Some("PAYMENT-1")));
+        assert_eq!(
+            first.accounts,
+            [
+                bic_field("57A", "BNPAFRPP"),
+                AccountField {
+                    tag: String::from("59"),
+                    value: String::from("FR1420041010050500013M02606"),
+                    kind: AccountKind::Iban,
+                    is_valid: Some(true),
+                },
+            ]
+        );
+        assert!(first.is_valid());
+
+        // The routing number of the clearing code has a wrong check digit
+        let second = &messages[1];
+        assert_eq!(second.accounts.len(), 3);
+        assert_eq!((second.accounts[0].kind, second.accounts[0].is_valid), (AccountKind::Domestic, Some(false)));
+        assert_eq!((second.accounts[1].kind, second.accounts[1].is_valid), (AccountKind::Bic, Some(true)));
+        assert_eq!((second.accounts[2].kind, second.accounts[2].is_valid), (AccountKind::Domestic, None));
+        assert!(!second.is_valid());
+    }
+
+    #[test]
+    fn test_mt940() {
+        let messages = scan_messages(MT940, &BankFiles::default()).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message_type.as_deref(), Some("940"));
+        assert_eq!(messages[0].accounts[0].kind, AccountKind::Iban);
+        assert!(messages[0].is_valid());
+        assert_eq!(messages[1].reference.as_deref(), Some("STATEMENT-2"));
+        assert_eq!(messages[1].accounts[0].value, "37040044/532013000");
+        assert_eq!(messages[1].accounts[0].kind, AccountKind::Domestic);
+    }
+
+    #[test]
+    fn test_account_with_currency() {
+        let bank_files = BankFiles { bank_directory: Some(Arc::new(bundesbank::tests::directory())), modulus_tables: None };
+        let messages = scan_messages(":20:A\n:25:DE89370400440532013000EUR\n-\n:20:B\n:25:37040044/532013000EUR\n", &bank_files).unwrap();
+        assert_eq!(
+            messages[0].accounts,
+            [AccountField {
+                tag: String::from("25"),
+                value: String::from("DE89370400440532013000"),
+                kind: AccountKind::Iban,
+                is_valid: Some(true),
+            }]
+        );
+        assert_eq!(
+            messages[1].accounts,
+            [AccountField {
+                tag: String::from("25"),
+                value: String::from("37040044/532013000"),
+                kind: AccountKind::Domestic,
+                is_valid: Some(true),
+            }]
+        );
+        // Only the bank directory that is passed in is used
+        let messages = scan_messages(":20:B\n:25:37040044/532013000EUR\n", &BankFiles::default()).unwrap();
+        assert_eq!(messages[0].accounts[0].is_valid, None);
+        assert_eq!(without_currency(String::from("EUR")), "EUR");
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(
+            fields(":20:REF\n:59:/DE89\nNAME\n\n:71A:SHA\n"),
+            [
+                (String::from("20"), String::from("REF")),
+                (String::from("59"), String::from("/DE89\nNAME")),
+                (String::from("71A"), String::from("SHA")),
+            ]
+        );
+        assert_eq!(scan_messages("no fields here", &BankFiles::default()), Err(SwiftMtError::NoMessages));
+        assert!(!scan_messages(":20:X\n:25:DE89370400440532013001\n", &BankFiles::default()).unwrap()[0].is_valid());
+    }
+}
@@ -0,0 +1,493 @@
+// Swiss QR-bill ("SPC") payloads, as defined in the Swiss Implementation Guidelines for the QR-bill.
+// See https://www.six-group.com/en/products-services/banking-services/payment-standardization/standards/qr-bill.html
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+use crate::iban::Iban;
+use crate::rf_reference::RfReference;
+use crate::{get_iban_vo_from_str, IbanVO};
+
+#[derive(Serialize, Clone, PartialEq, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    // "S" for structured or "K" for combined address lines
+    pub address_type: String,
+    pub name: String,
+    pub street_or_line1: String,
+    pub building_or_line2: String,
+    pub postal_code: String,
+    pub town: String,
+    pub country: String,
+}
+
+impl Address {
+    fn is_empty(&self) -> bool {
+        self.address_type.is_empty() && self.name.is_empty()
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwissQrBill {
+    pub version: String,
+    pub coding: String,
+    pub iban: String,
+    pub creditor: Address,
+    // Reserved for future use, the guidelines don't allow it to be filled in
+    pub ultimate_creditor: Option<Address>,
+    pub amount: String,
+    pub currency: String,
+    pub debtor: Option<Address>,
+    // QRR, SCOR or NON
+    pub reference_type: String,
+    pub reference: String,
+    pub message: String,
+    pub billing_information: String,
+    pub alternative_procedures: Vec<String>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SwissQrError {
+    NotASwissQrBill,
+    MissingFields(usize),
+    MissingTrailer,
+    UnsupportedVersion(String),
+    UnsupportedCoding(String),
+    InvalidIban(String),
+    NotSwissIban(String),
+    InvalidAddress { party: &'static str, reason: &'static str },
+    InvalidAmount(String),
+    UnsupportedCurrency(String),
+    UnknownReferenceType(String),
+    QrIbanRequiresQrReference,
+    QrReferenceRequiresQrIban,
+    InvalidQrReference(String),
+    InvalidCreditorReference(String),
+    UnexpectedReference(String),
+    FieldTooLong { field: &'static str, max: usize },
+    TooManyAlternativeProcedures(usize),
+    UltimateCreditorNotAllowed,
+}
+
+impl fmt::Display for SwissQrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwissQrError::NotASwissQrBill => write!(f, "the payload is not a Swiss QR-bill"),
+            SwissQrError::MissingFields(found) => {
+                write!(f, "the QR-bill has {} fields, at least 31 are required", found)
+            }
+            SwissQrError::MissingTrailer => write!(f, "the QR-bill trailer \"EPD\" is missing"),
+            SwissQrError::UnsupportedVersion(version) => {
+                write!(f, "version {} is not supported, expected 0200", version)
+            }
+            SwissQrError::UnsupportedCoding(coding) => {
+                write!(f, "coding type {} is not supported, expected 1", coding)
+            }
+            SwissQrError::InvalidIban(iban) => write!(f, "{} is not a valid IBAN", iban),
+            SwissQrError::NotSwissIban(iban) => {
+                write!(f, "{} is not a Swiss or Liechtenstein IBAN", iban)
+            }
+            SwissQrError::InvalidAddress { party, reason } => {
+                write!(f, "the {} address is invalid: {}", party, reason)
+            }
+            SwissQrError::InvalidAmount(amount) => {
+                write!(f, "{} is not an amount between 0.01 and 999999999.99", amount)
+            }
+            SwissQrError::UnsupportedCurrency(currency) => {
+                write!(f, "{} is not supported, use CHF or EUR", currency)
+            }
+            SwissQrError::UnknownReferenceType(reference_type) => {
+                write!(f, "{} is not a reference type, use QRR, SCOR or NON", reference_type)
+            }
+            SwissQrError::QrIbanRequiresQrReference => {
+                write!(f, "a QR-IBAN must be used with a QR reference (QRR)")
+            }
+            SwissQrError::QrReferenceRequiresQrIban => {
+                write!(f, "a QR reference can only be used with a QR-IBAN")
+            }
+            SwissQrError::InvalidQrReference(reference) => {
+                write!(f, "{} is not a valid QR reference", reference)
+            }
+            SwissQrError::InvalidCreditorReference(reference) => {
+                write!(f, "{} is not a valid creditor reference", reference)
+            }
+            SwissQrError::UnexpectedReference(reference) => {
+                write!(f, "reference type NON cannot have the reference {}", reference)
+            }
+            SwissQrError::FieldTooLong { field, max } => {
+                write!(f, "{} can be at most {} characters", field, max)
+            }
+            SwissQrError::TooManyAlternativeProcedures(count) => {
+                write!(f, "{} alternative procedures were given, at most 2 are allowed", count)
+            }
+            SwissQrError::UltimateCreditorNotAllowed => {
+                write!(f, "the ultimate creditor fields are reserved and must be empty")
+            }
+        }
+    }
+}
+
+impl Serialize for SwissQrError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+// The fixed part of the payload, up to and including the "EPD" trailer
+const REQUIRED_FIELDS: usize = 31;
+
+fn read_address(fields: &[&str]) -> Address {
+    Address {
+        address_type: fields[0].to_string(),
+        name: fields[1].to_string(),
+        street_or_line1: fields[2].to_string(),
+        building_or_line2: fields[3].to_string(),
+        postal_code: fields[4].to_string(),
+        town: fields[5].to_string(),
+        country: fields[6].to_string(),
+    }
+}
+
+pub fn parse(payload: &str) -> Result<SwissQrBill, SwissQrError> {
+    // Both CR+LF and LF are allowed as separators
+    let fields: Vec<&str> = payload.lines().map(str::trim).collect();
+    if fields.first() != Some(&"SPC") {
+        return Err(SwissQrError::NotASwissQrBill);
+    }
+    if fields.len() < REQUIRED_FIELDS {
+        return Err(SwissQrError::MissingFields(fields.len()));
+    }
+    if fields[30] != "EPD" {
+        return Err(SwissQrError::MissingTrailer);
+    }
+
+    // Fields 11..18 are reserved for the ultimate creditor, `validate` reports them when filled in
+    let ultimate_creditor = read_address(&fields[11..18]);
+    let debtor = read_address(&fields[20..27]);
+    Ok(SwissQrBill {
+        version: fields[1].to_string(),
+        coding: fields[2].to_string(),
+        iban: fields[3].to_string(),
+        creditor: read_address(&fields[4..11]),
+        ultimate_creditor: if fields[11..18].iter().all(|f| f.is_empty()) { None } else { Some(ultimate_creditor) },
+        amount: fields[18].to_string(),
+        currency: fields[19].to_string(),
+        debtor: if debtor.is_empty() { None } else { Some(debtor) },
+        reference_type: fields[27].to_string(),
+        reference: fields[28].to_string(),
+        message: fields[29].to_string(),
+        billing_information: fields.get(31).unwrap_or(&"").to_string(),
+        alternative_procedures: fields.iter().skip(32).map(|f| f.to_string()).collect(),
+    })
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwissQrReport {
+    pub bill: SwissQrBill,
+    pub iban: IbanVO,
+    pub is_qr_iban: bool,
+    pub problems: Vec<SwissQrError>,
+}
+
+// Whether the text is a valid QR-IBAN, see `Iban::is_qr_iban`
+pub fn is_qr_iban(iban: &str) -> bool {
+    iban.parse::<Iban>().is_ok_and(|iban| iban.is_qr_iban())
+}
+
+const RECURSIVE_MOD_10_TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+
+// Check digit of the ESR/QR reference, "Modulo 10 recursive"
+pub fn mod10_recursive_check_digit(digits: &str) -> Option<u32> {
+    let mut carry = 0;
+    for c in digits.chars() {
+        carry = RECURSIVE_MOD_10_TABLE[((carry + c.to_digit(10)?) % 10) as usize];
+    }
+    Some((10 - carry) % 10)
+}
+
+fn is_valid_qr_reference(reference: &str) -> bool {
+    reference.len() == 27
+        && reference.chars().all(|c| c.is_ascii_digit())
+        && mod10_recursive_check_digit(&reference[..26])
+            == reference[26..].chars().next().and_then(|c| c.to_digit(10))
+}
+
+// The QR-bill only allows creditor references in electronic format
+fn is_valid_creditor_reference(reference: &str) -> bool {
+    RfReference::parse(reference).is_ok_and(|parsed| parsed.electronic_str() == reference)
+}
+
+fn check_length(
+    problems: &mut Vec<SwissQrError>,
+    field: &'static str,
+    value: &str,
+    max: usize,
+) {
+    if value.chars().count() > max {
+        problems.push(SwissQrError::FieldTooLong { field, max });
+    }
+}
+
+fn check_address(problems: &mut Vec<SwissQrError>, party: &'static str, address: &Address) {
+    let mut invalid = |reason| problems.push(SwissQrError::InvalidAddress { party, reason });
+    if address.name.is_empty() {
+        invalid("the name is missing");
+    }
+    if address.name.chars().count() > 70 || address.street_or_line1.chars().count() > 70 {
+        invalid("the name and street can be at most 70 characters");
+    }
+    match address.address_type.as_str() {
+        "S" => {
+            if address.building_or_line2.chars().count() > 16
+                || address.postal_code.chars().count() > 16
+                || address.town.chars().count() > 35
+            {
+                invalid("the building number, postal code or town is too long");
+            }
+            if address.postal_code.is_empty() || address.town.is_empty() {
+                invalid("the postal code and town are required");
+            }
+        }
+        "K" => {
+            if address.building_or_line2.is_empty() || address.building_or_line2.chars().count() > 70 {
+                invalid("the second address line is required and can be at most 70 characters");
+            }
+            if !address.postal_code.is_empty() || !address.town.is_empty() {
+                invalid("combined addresses cannot have a separate postal code or town");
+            }
+        }
+        _ => invalid("the address type must be S or K"),
+    }
+    if address.country.len() != 2 || !address.country.chars().all(|c| c.is_ascii_uppercase()) {
+        invalid("the country must be a two letter code");
+    }
+}
+
+// Checks a parsed QR-bill against the rules of the implementation guidelines, returning every problem found
+pub fn validate(bill: &SwissQrBill) -> Vec<SwissQrError> {
+    let mut problems = Vec::new();
+
+    if bill.version != "0200" {
+        problems.push(SwissQrError::UnsupportedVersion(bill.version.clone()));
+    }
+    if bill.coding != "1" {
+        problems.push(SwissQrError::UnsupportedCoding(bill.coding.clone()));
+    }
+
+    let iban = bill.iban.parse::<Iban>().ok();
+    match &iban {
+        None => problems.push(SwissQrError::InvalidIban(bill.iban.clone())),
+        Some(iban) if !["CH", "LI"].contains(&iban.country_code()) => {
+            problems.push(SwissQrError::NotSwissIban(bill.iban.clone()))
+        }
+        Some(_) => {}
+    }
+    // The pairing of QR-IBAN and QR reference is only checked for valid IBANs
+    let accepts_reference = |is_qr_reference| iban.as_ref().is_none_or(|iban| iban.accepts_reference(is_qr_reference));
+
+    check_address(&mut problems, "creditor", &bill.creditor);
+    if bill.ultimate_creditor.is_some() {
+        problems.push(SwissQrError::UltimateCreditorNotAllowed);
+    }
+    if let Some(debtor) = &bill.debtor {
+        check_address(&mut problems, "debtor", debtor);
+    }
+
+    if !bill.amount.is_empty() {
+        match crate::epc_qr::parse_amount_cents(&bill.amount) {
+            Some(cents) if cents > 0 && cents <= 99_999_999_999 => {}
+            _ => problems.push(SwissQrError::InvalidAmount(bill.amount.clone())),
+        }
+    }
+    if bill.currency != "CHF" && bill.currency != "EUR" {
+        problems.push(SwissQrError::UnsupportedCurrency(bill.currency.clone()));
+    }
+
+    match bill.reference_type.as_str() {
+        "QRR" => {
+            if !accepts_reference(true) {
+                problems.push(SwissQrError::QrReferenceRequiresQrIban);
+            }
+            if !is_valid_qr_reference(&bill.reference) {
+                problems.push(SwissQrError::InvalidQrReference(bill.reference.clone()));
+            }
+        }
+        "SCOR" => {
+            if !accepts_reference(false) {
+                problems.push(SwissQrError::QrIbanRequiresQrReference);
+            }
+            if !is_valid_creditor_reference(&bill.reference) {
+                problems.push(SwissQrError::InvalidCreditorReference(bill.reference.clone()));
+            }
+        }
+        "NON" => {
+            if !accepts_reference(false) {
+                problems.push(SwissQrError::QrIbanRequiresQrReference);
+            }
+            if !bill.reference.is_empty() {
+                problems.push(SwissQrError::UnexpectedReference(bill.reference.clone()));
+            }
+        }
+        other => problems.push(SwissQrError::UnknownReferenceType(other.to_string())),
+    }
+
+    check_length(&mut problems, "the message", &bill.message, 140);
+    // The message and billing information share the space of 140 characters
+    check_length(
+        &mut problems,
+        "the message and billing information together",
+        &(bill.message.clone() + &bill.billing_information),
+        140,
+    );
+    if bill.alternative_procedures.len() > 2 {
+        problems.push(SwissQrError::TooManyAlternativeProcedures(
+            bill.alternative_procedures.len(),
+        ));
+    }
+    for procedure in &bill.alternative_procedures {
+        check_length(&mut problems, "an alternative procedure", procedure, 100);
+    }
+    problems
+}
+
+pub fn parse_and_validate(payload: &str) -> Result<SwissQrReport, SwissQrError> {
+    let bill = parse(payload)?;
+    Ok(SwissQrReport {
+        iban: get_iban_vo_from_str(&bill.iban),
+        is_qr_iban: is_qr_iban(&bill.iban),
+        problems: validate(&bill),
+        bill,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Example from the Swiss Implementation Guidelines, with a QR reference
+    const QR_BILL: &str = "SPC\n0200\n1\nCH4431999123000889012\nS\nRobert Schneider AG\nRue du Lac\n1268\n2501\nBiel\nCH\n\n\n\n\n\n\n\n1949.75\nCHF\nS\nPia-Maria Rutschmann-Schnyder\nGrosse Marktgasse\n28\n9400\nRorschach\nCH\nQRR\n210000000003139471430009017\nOrder dated 18.06.2020\nEPD\n//S1/10/10201409/11/200701/20/140.000-53/30/102673831/31/200615/32/7.7/33/7.7:139.40/40/0:30\nName AV1: UV;UltraPay005;12345\nName AV2: XY;XYService;54321";
+
+    #[test]
+    fn test_parse() {
+        let bill = parse(QR_BILL).unwrap();
+        assert_eq!(bill.iban, "CH4431999123000889012");
+        assert_eq!(bill.creditor.name, "Robert Schneider AG");
+        assert_eq!(bill.creditor.country, "CH");
+        assert_eq!(bill.amount, "1949.75");
+        assert_eq!(bill.currency, "CHF");
+        assert_eq!(bill.debtor.unwrap().town, "Rorschach");
+        assert_eq!(bill.reference_type, "QRR");
+        assert_eq!(bill.reference, "210000000003139471430009017");
+        assert_eq!(bill.message, "Order dated 18.06.2020");
+        assert_eq!(bill.alternative_procedures.len(), 2);
+    }
+
+    #[test]
+    fn test_is_qr_iban() {
+        assert!(is_qr_iban("CH4431999123000889012"));
+        assert!(is_qr_iban("CH44 3199 9123 0008 8901 2"));
+        assert!(!is_qr_iban("CH9300762011623852957"));
+        assert!(!is_qr_iban("DE89370400440532013000"));
+        assert!(!is_qr_iban("CH"));
+    }
+
+    #[test]
+    fn test_mod10_recursive_check_digit() {
+        assert_eq!(mod10_recursive_check_digit("21000000000313947143000901"), Some(7));
+        assert_eq!(mod10_recursive_check_digit("00000000000000000000000000"), Some(0));
+        assert_eq!(mod10_recursive_check_digit("12A"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let report = parse_and_validate(QR_BILL).unwrap();
+        assert!(report.is_qr_iban);
+        assert!(report.iban.is_valid());
+        assert_eq!(report.problems, vec![]);
+
+        // A QR reference with a normal IBAN
+        let normal_iban = QR_BILL.replace("CH4431999123000889012", "CH9300762011623852957");
+        assert_eq!(
+            validate(&parse(&normal_iban).unwrap()),
+            vec![SwissQrError::QrReferenceRequiresQrIban]
+        );
+
+        // A QR-IBAN without a reference
+        let without_reference = QR_BILL
+            .replace("QRR\n210000000003139471430009017", "NON\n");
+        assert_eq!(
+            validate(&parse(&without_reference).unwrap()),
+            vec![SwissQrError::QrIbanRequiresQrReference]
+        );
+
+        // The pairing is not checked for an invalid IBAN
+        let invalid_iban = QR_BILL.replace("CH4431999123000889012", "CH4431999123000889013");
+        assert_eq!(
+            validate(&parse(&invalid_iban).unwrap()),
+            vec![SwissQrError::InvalidIban(String::from("CH4431999123000889013"))]
+        );
+
+        let wrong_check_digit = QR_BILL.replace("210000000003139471430009017", "210000000003139471430009018");
+        assert_eq!(
+            validate(&parse(&wrong_check_digit).unwrap()),
+            vec![SwissQrError::InvalidQrReference(String::from("210000000003139471430009018"))]
+        );
+    }
+
+    #[test]
+    fn test_validate_creditor_reference() {
+        let scor = QR_BILL
+            .replace("CH4431999123000889012", "CH9300762011623852957")
+            .replace("QRR\n210000000003139471430009017", "SCOR\nRF18539007547034");
+        assert_eq!(validate(&parse(&scor).unwrap()), vec![]);
+
+        let wrong_check_digits = scor.replace("RF18539007547034", "RF19539007547034");
+        assert_eq!(
+            validate(&parse(&wrong_check_digits).unwrap()),
+            vec![SwissQrError::InvalidCreditorReference(String::from("RF19539007547034"))]
+        );
+
+        let foreign = scor.replace("CH9300762011623852957", "DE89370400440532013000");
+        assert_eq!(
+            validate(&parse(&foreign).unwrap()),
+            vec![SwissQrError::NotSwissIban(String::from("DE89370400440532013000"))]
+        );
+    }
+
+    #[test]
+    fn test_validate_fields() {
+        let bill = QR_BILL
+            .replace("\nS\nRobert Schneider AG", "\nX\nRobert Schneider AG")
+            .replace("1949.75\nCHF", "1949.755\nUSD");
+        assert_eq!(
+            validate(&parse(&bill).unwrap()),
+            vec![
+                SwissQrError::InvalidAddress {
+                    party: "creditor",
+                    reason: "the address type must be S or K"
+                },
+                SwissQrError::InvalidAmount(String::from("1949.755")),
+                SwissQrError::UnsupportedCurrency(String::from("USD")),
+            ]
+        );
+
+        let ultimate_creditor = QR_BILL.replace("CH\n\n\n\n\n\n\n\n1949.75", "CH\nS\nPia Muster\n\n\n9400\nRorschach\nCH\n1949.75");
+        let bill = parse(&ultimate_creditor).unwrap();
+        assert_eq!(bill.ultimate_creditor.as_ref().map(|address| address.name.as_str()), Some("Pia Muster"));
+        assert_eq!(validate(&bill), vec![SwissQrError::UltimateCreditorNotAllowed]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("BCD\n002"), Err(SwissQrError::NotASwissQrBill));
+        assert_eq!(parse("SPC\n0200\n1"), Err(SwissQrError::MissingFields(3)));
+        let without_trailer = QR_BILL.replace("EPD", "XXX");
+        assert_eq!(parse(&without_trailer), Err(SwissQrError::MissingTrailer));
+    }
+}
@@ -0,0 +1,182 @@
+// Finding IBANs in free text, like emails and invoices. IBANs are found in the electronic format
+// and in the paper format with groups of four, also when they are broken over several lines.
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::{country_data, get_iban_vo_from_str, IbanVO};
+
+// The length of the shortest IBANs, shorter candidates are not reported
+const MIN_LENGTH: usize = 15;
+const GROUP_LENGTH: usize = 4;
+const PREFIX: &str = "IBAN";
+
+#[derive(PartialEq, Debug)]
+pub struct IbanMatch {
+    // Byte offsets of the IBAN in the text, the end is exclusive
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    // The IBAN as it is written in the text
+    pub text: String,
+    pub has_iban_prefix: bool,
+    pub validation: IbanVO,
+}
+
+impl Serialize for IbanMatch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("IbanMatch", 6)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("end", &self.end)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("text", &self.text)?;
+        state.serialize_field("hasIbanPrefix", &self.has_iban_prefix)?;
+        state.serialize_field("validation", &self.validation)?;
+        state.end()
+    }
+}
+
+// IBANs are written with upper case letters, which keeps the words that follow them out
+fn is_iban_char(c: char) -> bool {
+    c.is_ascii_digit() || c.is_ascii_uppercase()
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n' | '\u{a0}')
+}
+
+// A country code and two check digits, not in the middle of a word
+fn starts_candidate(chars: &[(usize, char)], i: usize) -> Option<usize> {
+    let code: String = chars.get(i..i + 2)?.iter().map(|&(_, c)| c).collect();
+    let is_start = chars.get(i + 2..i + 4)?.iter().all(|&(_, c)| c.is_ascii_digit())
+        && code.chars().all(|c| c.is_ascii_uppercase())
+        && (i == 0 || !chars[i - 1].1.is_alphanumeric());
+    if !is_start {
+        return None;
+    }
+    country_data::get_country_codes_and_length()
+        .get(country_data::get_format_country(&code))
+        .copied()
+}
+
+// Whether the text before the IBAN ends with "IBAN", optionally followed by a colon, dot or "-Nr."
+fn has_iban_prefix(before: &str) -> bool {
+    let before = before
+        .trim_end()
+        .trim_end_matches([':', '.'])
+        .trim_end_matches("-Nr")
+        .trim_end_matches(" Nr")
+        .trim_end();
+    before.len() >= PREFIX.len()
+        && before.is_char_boundary(before.len() - PREFIX.len())
+        && before[before.len() - PREFIX.len()..].eq_ignore_ascii_case(PREFIX)
+}
+
+pub fn find_ibans(text: &str) -> Vec<IbanMatch> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let expected_length = match starts_candidate(&chars, i) {
+            Some(length) => length,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut iban = String::new();
+        let mut j = i;
+        loop {
+            let group_start = j;
+            while j < chars.len() && is_iban_char(chars[j].1) {
+                iban.push(chars[j].1);
+                j += 1;
+            }
+            if iban.len() >= expected_length {
+                break;
+            }
+            // Groups continue after a line break, or after a single space in the paper format
+            let mut k = j;
+            while k < chars.len() && is_separator(chars[k].1) {
+                k += 1;
+            }
+            let separator: String = chars[j..k].iter().map(|&(_, c)| c).collect();
+            let continues = separator.contains('\n')
+                || (separator.chars().count() == 1 && j - group_start == GROUP_LENGTH);
+            if k == j || !continues || k == chars.len() || !is_iban_char(chars[k].1) {
+                break;
+            }
+            j = k;
+        }
+
+        if iban.len() < MIN_LENGTH {
+            i += 1;
+            continue;
+        }
+        let start = chars[i].0;
+        let end = chars.get(j).map_or(text.len(), |&(offset, _)| offset);
+        matches.push(IbanMatch {
+            start,
+            end,
+            line: text[..start].matches('\n').count() + 1,
+            text: text[start..end].to_string(),
+            has_iban_prefix: has_iban_prefix(&text[..start]),
+            validation: get_iban_vo_from_str(&iban),
+        });
+        i = j;
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_find_ibans() {
+        let text = "Dear customer,\n\
+                    please pay to IBAN: DE89 3704 0044 0532 0130 00 (Commerzbank)\n\
+                    or GB29NWBK60161331926819 before 1 March.\n\
+                    Our old account DE89370400440532013001 is closed.";
+        let matches = find_ibans(text);
+        assert_eq!(matches.len(), 3);
+
+        assert_eq!(matches[0].text, "DE89 3704 0044 0532 0130 00");
+        assert_eq!(&text[matches[0].start..matches[0].end], matches[0].text);
+        assert_eq!(matches[0].line, 2);
+        assert!(matches[0].has_iban_prefix);
+        assert!(matches[0].validation.is_valid());
+
+        assert_eq!(matches[1].text, "GB29NWBK60161331926819");
+        assert!(!matches[1].has_iban_prefix);
+        assert!(matches[1].validation.is_valid());
+
+        assert_eq!(matches[2].line, 4);
+        assert!(!matches[2].validation.is_valid());
+    }
+
+    #[test]
+    fn test_iban_split_over_lines() {
+        let matches = find_ibans("IBAN-Nr.: DE89 3704 0044\n  0532 0130 00\nThank you");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].text, "DE89 3704 0044\n  0532 0130 00");
+        assert!(matches[0].has_iban_prefix);
+        assert!(matches[0].validation.is_valid());
+
+        let matches = find_ibans("Konto:DE8937040044\r\n0532013000");
+        assert!(matches[0].validation.is_valid());
+    }
+
+    #[test]
+    fn test_no_false_candidates() {
+        assert!(find_ibans("Invoice DE12 from 2023, order AB1234567890123456").is_empty());
+        // Words after an incomplete IBAN are not taken as part of it
+        let matches = find_ibans("DE89 3704 0044 0532 0130 and more text");
+        assert_eq!(matches[0].text, "DE89 3704 0044 0532 0130");
+        assert!(!matches[0].validation.is_correct_length);
+        assert!(find_ibans("XDE89370400440532013000 ÖDE89370400440532013000").is_empty());
+    }
+}
@@ -0,0 +1,510 @@
+// UK account numbers. Vocalink publishes the modulus checks of the sort codes in two files. The
+// valacdos file has a line per range of sort codes with the check, MOD10, MOD11 or DBLAL (double
+// alternate), the weights of the 6 digits of the sort code and the 8 digits of the account number,
+// and an exception code for the checks that deviate from the standard ones. Some ranges have two
+// lines, and then both checks are done. The scsubtab file lists the sort codes that are checked as
+// another sort code under exception 5.
+//
+// Account numbers of sort codes that aren't in the valacdos file can't be checked, and are reported
+// as not checked.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use serde::ser::{Serialize, Serializer};
+
+use crate::national_check::AccountCheck;
+
+// Exception 2 weights when a is not 0, and when g is also 9
+const EXCEPTION_2_WEIGHTS: [i32; 14] = [0, 0, 1, 2, 5, 3, 6, 4, 8, 7, 10, 9, 3, 1];
+const EXCEPTION_2_G9_WEIGHTS: [i32; 14] = [0, 0, 0, 0, 0, 0, 0, 0, 8, 7, 10, 9, 3, 1];
+// The sort codes that exceptions 8 and 9 check the account numbers with
+const EXCEPTION_8_SORT_CODE: &str = "090126";
+const EXCEPTION_9_SORT_CODE: &str = "309634";
+
+// The modulus tables used for the national account check of UK IBANs
+static TABLES: RwLock<Option<Arc<ModulusTables>>> = RwLock::new(None);
+
+#[derive(PartialEq, Debug)]
+pub enum VocalinkError {
+    UnreadableFile(String),
+    InvalidLine(usize),
+    NoModulusTables,
+}
+
+impl fmt::Display for VocalinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VocalinkError::UnreadableFile(msg) => write!(f, "the modulus file could not be read: {}", msg),
+            VocalinkError::InvalidLine(line) => write!(f, "line {} of the modulus file is invalid", line),
+            VocalinkError::NoModulusTables => write!(f, "no Vocalink modulus file (valacdos) has been loaded"),
+        }
+    }
+}
+
+impl Serialize for VocalinkError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        crate::serialize_error(self, serializer)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Method {
+    Mod10,
+    Mod11,
+    DoubleAlternate,
+}
+
+impl Method {
+    fn name(&self) -> &'static str {
+        match self {
+            Method::Mod10 => "MOD10",
+            Method::Mod11 => "MOD11",
+            Method::DoubleAlternate => "DBLAL",
+        }
+    }
+}
+
+// A line of the valacdos file
+#[derive(Clone, PartialEq, Debug)]
+pub struct ModulusCheck {
+    pub start: String,
+    pub end: String,
+    pub method: Method,
+    // The weights of the digits u v w x y z of the sort code and a b c d e f g h of the account
+    pub weights: [i32; 14],
+    pub exception: Option<u8>,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ModulusTables {
+    checks: Vec<ModulusCheck>,
+    substitutions: HashMap<String, String>,
+}
+
+fn is_sort_code(value: &str) -> bool {
+    value.len() == 6 && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_checks(text: &str) -> Result<Vec<ModulusCheck>, VocalinkError> {
+    let mut checks = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let invalid = || VocalinkError::InvalidLine(i + 1);
+        if !(17..=18).contains(&fields.len()) || !is_sort_code(fields[0]) || !is_sort_code(fields[1]) {
+            return Err(invalid());
+        }
+        let method = match fields[2] {
+            "MOD10" => Method::Mod10,
+            "MOD11" => Method::Mod11,
+            "DBLAL" => Method::DoubleAlternate,
+            _ => return Err(invalid()),
+        };
+        let mut weights = [0; 14];
+        for (weight, field) in weights.iter_mut().zip(&fields[3..17]) {
+            *weight = field.parse().map_err(|_| invalid())?;
+        }
+        let exception = match fields.get(17) {
+            Some(field) => Some(field.parse().map_err(|_| invalid())?),
+            None => None,
+        };
+        checks.push(ModulusCheck {
+            start: fields[0].to_string(),
+            end: fields[1].to_string(),
+            method,
+            weights,
+            exception,
+        });
+    }
+    Ok(checks)
+}
+
+fn parse_substitutions(text: &str) -> Result<HashMap<String, String>, VocalinkError> {
+    let mut substitutions = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [] => {}
+            [sort_code, substitute] if is_sort_code(sort_code) && is_sort_code(substitute) => {
+                substitutions.insert(sort_code.to_string(), substitute.to_string());
+            }
+            _ => return Err(VocalinkError::InvalidLine(i + 1)),
+        }
+    }
+    Ok(substitutions)
+}
+
+// The sort code and the account number as 14 digits, account numbers of 6 and 7 digits are padded
+// with zeros on the left
+fn account_digits(sort_code: &str, account_number: &str) -> Option<[i32; 14]> {
+    if !is_sort_code(sort_code) || !(6..=8).contains(&account_number.len()) {
+        return None;
+    }
+    let mut digits = [0; 14];
+    let padded = format!("{}{:0>8}", sort_code, account_number);
+    for (digit, c) in digits.iter_mut().zip(padded.chars()) {
+        *digit = c.to_digit(10)? as i32;
+    }
+    Some(digits)
+}
+
+fn set_sort_code(digits: &mut [i32; 14], sort_code: &str) {
+    for (digit, c) in digits.iter_mut().zip(sort_code.chars()) {
+        *digit = c.to_digit(10).unwrap_or(0) as i32;
+    }
+}
+
+impl ModulusTables {
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    // The one or two checks of the sort code, in the order of the file
+    fn checks_of(&self, sort_code: &str) -> Vec<&ModulusCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.start.as_str() <= sort_code && sort_code <= check.end.as_str())
+            .take(2)
+            .collect()
+    }
+
+    pub fn check_account(&self, sort_code: &str, account_number: &str) -> AccountCheck {
+        let normalize = |value: &str| -> String { value.chars().filter(|c| !c.is_whitespace() && *c != '-').collect() };
+        let (sort_code, account_number) = (normalize(sort_code), normalize(account_number));
+        let checks = self.checks_of(&sort_code);
+        AccountCheck {
+            method: (!checks.is_empty())
+                .then(|| checks.iter().map(|check| check.method.name()).collect::<Vec<_>>().join(", ")),
+            is_valid: self.is_valid(&sort_code, &account_number, &checks),
+            bank_code: sort_code,
+            account_number,
+            bank_name: None,
+            bic: None,
+        }
+    }
+
+    // The check of the BBAN of a UK IBAN, the bank identifier is followed by the sort code and the
+    // account number
+    pub fn check_bban(&self, bban: &str) -> Option<AccountCheck> {
+        Some(self.check_account(bban.get(4..10)?, bban.get(10..18)?))
+    }
+
+    // `None` if the sort code has no checks, malformed sort codes and account numbers are invalid
+    fn is_valid(&self, sort_code: &str, account_number: &str, checks: &[&ModulusCheck]) -> Option<bool> {
+        let digits = match account_digits(sort_code, account_number) {
+            Some(digits) => digits,
+            None => return Some(false),
+        };
+        let (first, second) = match checks {
+            [] => return None,
+            [first] => (*first, None),
+            [first, second, ..] => (*first, Some(*second)),
+        };
+        let (a, c, g, h) = (digits[6], digits[8], digits[12], digits[13]);
+        // Exception 6: foreign currency accounts can't be checked
+        if first.exception == Some(6) && (4..=8).contains(&a) && g == h {
+            return Some(true);
+        }
+        let first_passes = self.passes(first, &digits);
+        let valid = match (first.exception, second) {
+            // Exceptions 2 and 9, 10 and 11, 12 and 13: the account is valid if either check passes
+            (Some(2 | 10 | 12), Some(second)) => first_passes || self.passes(second, &digits),
+            // Exception 3: the second check isn't done if c is 6 or 9
+            (_, Some(second)) if second.exception == Some(3) && (c == 6 || c == 9) => first_passes,
+            (_, Some(second)) => first_passes && self.passes(second, &digits),
+            // Exception 14: if the check fails and h is 0, 1 or 9, h is dropped and the check is
+            // done again with the account number shifted right
+            (Some(14), None) if !first_passes && matches!(h, 0 | 1 | 9) => {
+                let mut shifted = digits;
+                shifted[7..14].copy_from_slice(&digits[6..13]);
+                shifted[6] = 0;
+                self.passes(first, &shifted)
+            }
+            (_, None) => first_passes,
+        };
+        Some(valid)
+    }
+
+    fn passes(&self, check: &ModulusCheck, digits: &[i32; 14]) -> bool {
+        let mut digits = *digits;
+        let mut weights = check.weights;
+        let (a, b, g, h) = (digits[6], digits[7], digits[12], digits[13]);
+        match check.exception {
+            Some(2) if a != 0 => weights = if g == 9 { EXCEPTION_2_G9_WEIGHTS } else { EXCEPTION_2_WEIGHTS },
+            Some(5) => {
+                let sort_code: String = digits[..6].iter().map(|digit| digit.to_string()).collect();
+                if let Some(substitute) = self.substitutions.get(&sort_code) {
+                    set_sort_code(&mut digits, substitute);
+                }
+            }
+            // Exception 7: the weights of u to b are 0 if g is 9, and exception 10 also if ab is 09 or 99
+            Some(7) if g == 9 => weights[..8].iter_mut().for_each(|weight| *weight = 0),
+            Some(10) if (a == 0 || a == 9) && b == 9 && g == 9 => weights[..8].iter_mut().for_each(|weight| *weight = 0),
+            Some(8) => set_sort_code(&mut digits, EXCEPTION_8_SORT_CODE),
+            Some(9) => set_sort_code(&mut digits, EXCEPTION_9_SORT_CODE),
+            _ => {}
+        }
+        let total: i32 = digits
+            .iter()
+            .zip(weights)
+            .map(|(digit, weight)| {
+                let product = digit * weight;
+                // The double alternate check adds the digits of every product
+                if check.method == Method::DoubleAlternate {
+                    product / 10 + product % 10
+                } else {
+                    product
+                }
+            })
+            .sum();
+        match (check.method, check.exception) {
+            // Exception 1: 27 is added to the total
+            (Method::DoubleAlternate, Some(1)) => (total + 27) % 10 == 0,
+            // Exception 4: the remainder is the check digits gh
+            (Method::Mod11, Some(4)) => total % 11 == g * 10 + h,
+            // Exception 5: the check digit of the first check is g and of the second check h
+            (Method::Mod11, Some(5)) => match total % 11 {
+                0 => g == 0,
+                1 => false,
+                remainder => 11 - remainder == g,
+            },
+            (Method::DoubleAlternate, Some(5)) => match total % 10 {
+                0 => h == 0,
+                remainder => 10 - remainder == h,
+            },
+            (Method::Mod11, _) => total % 11 == 0,
+            (Method::Mod10 | Method::DoubleAlternate, _) => total % 10 == 0,
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, VocalinkError> {
+    let bytes = std::fs::read(path).map_err(|e| VocalinkError::UnreadableFile(e.to_string()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Replaces one of the files in the loaded tables, the other one is kept
+fn update_tables(update: impl FnOnce(&mut ModulusTables)) {
+    let mut tables = TABLES.write().unwrap_or_else(|e| e.into_inner());
+    let mut updated = tables.as_deref().cloned().unwrap_or_default();
+    update(&mut updated);
+    *tables = Some(Arc::new(updated));
+}
+
+// Returns the number of sort code ranges
+pub fn load_valacdos(path: &Path) -> Result<usize, VocalinkError> {
+    let checks = parse_checks(&read_file(path)?)?;
+    let ranges = checks.len();
+    update_tables(|tables| tables.checks = checks);
+    Ok(ranges)
+}
+
+// Returns the number of substituted sort codes
+pub fn load_scsubtab(path: &Path) -> Result<usize, VocalinkError> {
+    let substitutions = parse_substitutions(&read_file(path)?)?;
+    let sort_codes = substitutions.len();
+    update_tables(|tables| tables.substitutions = substitutions);
+    Ok(sort_codes)
+}
+
+// Loads either file, the substitution table is recognized by its name
+pub fn load_modulus_file(path: &Path) -> Result<usize, VocalinkError> {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if name.contains("scsubtab") {
+        load_scsubtab(path)
+    } else {
+        load_valacdos(path)
+    }
+}
+
+pub fn modulus_tables() -> Result<Arc<ModulusTables>, VocalinkError> {
+    TABLES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .filter(|tables| !tables.is_empty())
+        .ok_or(VocalinkError::NoModulusTables)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use super::*;
+
+    const VALACDOS: &str = "\
+100000 109999 MOD10    0    0    0    0    0    0    7    1    3    7    1    3    7    1
+200000 209999 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1
+200000 209999 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1
+300000 309999 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1   6
+300000 309999 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1   6
+400000 409999 MOD11    2    1    2    1    2    1    8    7    6    5    4    3    2    1  10
+400000 409999 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1  11
+500000 509999 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1  14
+600000 609999 MOD11    7    6    5    4    3    2    7    6    5    4    3    2    0    0   5
+600000 609999 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    0    1   5
+";
+    const SCSUBTAB: &str = "600001 600100\n";
+
+    // Lines for the sort codes of the test cases of the Vocalink specification. The weights of the
+    // worked examples, the standard checks and exception 5 with the substitute of 938600, are those
+    // of the specification. The weights of the other lines are chosen so that the checks and
+    // exceptions of the cases give the published results, they aren't copied from the valacdos file
+    const SPECIFICATION_VALACDOS: &str = "\
+070000 074999 MOD11    1    3    7    1    3    7    1    3    7    1    3    7    1    0  12
+070000 074999 MOD10    7    1    3    7    1    3    7    1    3    7    1    3    7    1  13
+086000 086999 MOD11    7    6    5    4    3    2    7    6    5    4    3    2    7    6   8
+089000 089999 MOD10    0    0    0    0    0    0    7    1    3    7    1    3    7    1
+107999 107999 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1
+118765 118765 DBLAL    0    0    2    1    2    1    2    1    2    1    2    1    2    1   1
+134020 134020 MOD11    0    0    0    0    0    0    7    5    6    3    4    2    0    0   4
+180002 180002 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1  14
+200915 200915 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1   6
+200915 200915 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1   6
+202900 203099 MOD11    0    0    0    0    0    0    0    7    6    5    4    3    2    1
+202900 203099 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1
+309070 309070 MOD11    0    0    7    6    5    4    3    2    7    6    5    4    3    2   2
+309070 309070 MOD11    0    0    0    7    1    3    7    1    3    7    1    3    7    1   9
+772700 772799 MOD11    0    0    1    2    5    3    6    4    8    7   10    9    3    1   7
+820000 827099 MOD10    0    0    0    0    0    0    7    1    3    7    1    3    7    1
+820000 827099 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1   3
+827100 827199 MOD11    0    0    0    0    0    0    8    7    6    5    4    3    2    1
+827100 827199 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1   3
+827200 827999 MOD10    0    0    0    0    0    0    7    1    3    7    1    3    7    1
+827200 827999 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    1   3
+871000 872999 MOD11    0    0    1    2    5    3    6    4    8    7   10    9    3    1  10
+871000 872999 MOD11    0    2    7    6    5    4    3    2    7    6    5    4    3    2  11
+938000 938696 MOD11    7    6    5    4    3    2    7    6    5    4    3    2    0    0   5
+938000 938696 DBLAL    2    1    2    1    2    1    2    1    2    1    2    1    2    0   5
+";
+    const SPECIFICATION_SCSUBTAB: &str = "938600 938611\n";
+
+    fn tables() -> ModulusTables {
+        ModulusTables {
+            checks: parse_checks(VALACDOS).unwrap(),
+            substitutions: parse_substitutions(SCSUBTAB).unwrap(),
+        }
+    }
+
+    pub(crate) fn specification_tables() -> ModulusTables {
+        ModulusTables {
+            checks: parse_checks(SPECIFICATION_VALACDOS).unwrap(),
+            substitutions: parse_substitutions(SPECIFICATION_SCSUBTAB).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let tables = tables();
+        assert_eq!(tables.len(), 10);
+        assert_eq!(tables.checks_of("200001").len(), 2);
+        assert_eq!(tables.checks_of("400000")[0].exception, Some(10));
+        assert_eq!(tables.substitutions.get("600001").map(String::as_str), Some("600100"));
+        assert_eq!(parse_checks("100000 109999 MOD12 0 0 0 0 0 0 7 1 3 7 1 3 7 1"), Err(VocalinkError::InvalidLine(1)));
+        assert_eq!(parse_substitutions("\n600001"), Err(VocalinkError::InvalidLine(2)));
+    }
+
+    #[test]
+    fn test_standard_checks() {
+        let tables = tables();
+        let check = tables.check_account("10-00-01", "12345601");
+        assert_eq!(check.is_valid, Some(true));
+        assert_eq!(check.method.as_deref(), Some("MOD10"));
+        assert_eq!(tables.check_account("100001", "12345602").is_valid, Some(false));
+        // Both checks have to pass
+        assert_eq!(tables.check_account("200001", "12345679").is_valid, Some(true));
+        assert_eq!(tables.check_account("200001", "12345628").is_valid, Some(false));
+        assert_eq!(tables.check_account("200001", "12345604").is_valid, Some(false));
+        // Account numbers of 7 digits start with 0
+        assert_eq!(tables.check_account("100001", "2345678").is_valid, tables.check_account("100001", "02345678").is_valid);
+        assert_eq!(tables.check_account("900000", "12345678").is_valid, None);
+        assert_eq!(tables.check_account("10000", "12345601").is_valid, Some(false));
+        assert_eq!(tables.check_account("100001", "1234560A").is_valid, Some(false));
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let tables = tables();
+        // Foreign currency accounts
+        assert_eq!(tables.check_account("300001", "51234577").is_valid, Some(true));
+        assert_eq!(tables.check_account("300001", "31234577").is_valid, Some(false));
+        // Either check passes, and the sort code doesn't count for ab 09 and g 9
+        assert_eq!(tables.check_account("400001", "12345603").is_valid, Some(true));
+        assert_eq!(tables.check_account("400001", "12345601").is_valid, Some(true));
+        assert_eq!(tables.check_account("400001", "09123498").is_valid, Some(true));
+        assert_eq!(tables.check_account("400001", "12345600").is_valid, Some(false));
+        // The account number is shifted when h is 0, 1 or 9
+        assert_eq!(tables.check_account("500001", "12345628").is_valid, Some(true));
+        assert_eq!(tables.check_account("500001", "01234550").is_valid, Some(true));
+        assert_eq!(tables.check_account("500001", "01234553").is_valid, Some(false));
+        // Substituted sort code, and check digits g and h
+        assert_eq!(tables.check_account("600001", "12340031").is_valid, Some(true));
+        assert_eq!(tables.check_account("600100", "12340031").is_valid, Some(true));
+        assert_eq!(tables.check_account("600002", "12340031").is_valid, Some(false));
+        assert_eq!(tables.check_account("600001", "12340032").is_valid, Some(false));
+        assert_eq!(tables.check_account("600001", "12340131").is_valid, Some(false));
+    }
+
+    #[test]
+    fn test_specification_cases() {
+        let tables = specification_tables();
+        let cases = [
+            ("089999", "66374958", true),
+            ("107999", "88837491", true),
+            ("202959", "63748472", true),
+            ("871427", "46238510", true),
+            ("872427", "46238510", true),
+            ("871427", "09123496", true),
+            ("871427", "99123496", true),
+            ("820000", "73688637", true),
+            ("827999", "73988638", true),
+            ("827101", "28748352", true),
+            ("134020", "63849203", true),
+            ("118765", "64371389", true),
+            ("200915", "41011166", true),
+            ("938611", "07806039", true),
+            ("938600", "42368003", true),
+            ("938063", "55065200", true),
+            ("772798", "99345694", true),
+            ("086090", "06774744", true),
+            ("309070", "02355688", true),
+            ("309070", "12345668", true),
+            ("309070", "12345677", true),
+            ("309070", "99345694", true),
+            ("938063", "15764273", false),
+            ("938063", "15764264", false),
+            ("938063", "15763217", false),
+            ("118765", "64371388", false),
+            ("203099", "66831036", false),
+            ("203099", "58716970", false),
+            ("089999", "66374959", false),
+            ("107999", "88837493", false),
+            ("074456", "12345112", true),
+            ("070116", "34012583", true),
+            ("074456", "11104102", true),
+            ("180002", "00000190", true),
+        ];
+        for (sort_code, account_number, is_valid) in cases {
+            let check = tables.check_account(sort_code, account_number);
+            assert_eq!(check.is_valid, Some(is_valid), "{} {}", sort_code, account_number);
+        }
+    }
+
+    #[test]
+    fn test_check_bban() {
+        let check = tables().check_bban("NWBK20000112345679").unwrap();
+        assert_eq!((check.bank_code.as_str(), check.account_number.as_str()), ("200001", "12345679"));
+        assert_eq!(check.method.as_deref(), Some("MOD11, DBLAL"));
+        assert_eq!(check.is_valid, Some(true));
+        assert_eq!(tables().check_bban("NWBK2000"), None);
+    }
+}